@@ -0,0 +1,349 @@
+use std::{path::Path, slice};
+
+pub struct OptionSpec {
+	pub name: &'static str,
+	pub value: Option<&'static str>, // placeholder for the option's value, None for switches
+	pub help: &'static str,
+	pub hidden: bool, // left out of the usage line, but still completed
+}
+
+pub const OPTIONS: &[OptionSpec] = &[
+	OptionSpec {
+		name: "encoding",
+		value: Some("enc"),
+		help: "re-encode author names and summaries (git blame --encoding)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "follow",
+		value: None,
+		help: "if the file didn't exist under this name at [rev], blame what it was called then",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "staged",
+		value: None,
+		help: "blame what's staged in the index (the default without [rev] when the file has staged changes)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "since",
+		value: Some("date"),
+		help: "blame older lines on the oldest commit since <date>, e.g. 2.weeks.ago (git blame --since)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "until",
+		value: Some("date"),
+		help: "blame as of the last commit before <date> that [rev] can reach",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "ignore-whitespace",
+		value: None,
+		help: "ignore whitespace changes when finding where lines came from (git blame -w)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "detect-moves",
+		value: None,
+		help: "blame lines moved within the file on where they were written (git blame -M)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "detect-copies",
+		value: None,
+		help: "and lines moved or copied from other files changed in the same commit (git blame -C)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "first-parent",
+		value: None,
+		help: "only follow the first parent of merges (git blame --first-parent)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "root",
+		value: None,
+		help: "blame lines from the root commit on it instead of marking them with ^ (git blame --root)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "ignore-revs-file",
+		value: Some("file"),
+		help: "skip the commits listed in <file>, e.g. reformatting (git blame --ignore-revs-file)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "abbrev",
+		value: Some("n"),
+		help: "show the first <n> characters of commit hashes, 4 to 40 (default 8)",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "no-alt-screen",
+		value: None,
+		help: "draw on the normal screen, leaving the last view in the scrollback after quitting",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "compare",
+		value: Some("rev"),
+		help: "show the blame at another revision side by side with the one at [rev]",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "show",
+		value: Some("rev"),
+		help: "print a commit the way enter shows it, through the pager, instead of starting the TUI",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "porcelain",
+		value: None,
+		help: "print the blame as tab-separated fields instead of starting the TUI",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "debug",
+		value: None,
+		help: "log git commands, timings and errors to $GIT_WHENCE_LOG or git-whence.log in the temp dir",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "version",
+		value: None,
+		help: "print version information for git-whence, libgit2 and git",
+		hidden: false,
+	},
+	OptionSpec {
+		name: "completions",
+		value: Some("shell"),
+		help: "print a bash, zsh or fish completion script",
+		hidden: true,
+	},
+];
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+	pub paths: Vec<String>, // each opens in its own tab
+	pub rev: Option<String>,
+	pub encoding: Option<String>,
+	pub since: Option<String>,
+	pub until: Option<String>,
+	pub abbrev: Option<usize>,
+	pub ignore_revs_file: Option<String>,
+	pub compare: Option<String>,
+	pub show: Option<String>,
+	pub completions: Option<String>,
+	pub version: bool,
+	pub debug: bool,
+	pub follow: bool,
+	pub porcelain: bool,
+	pub ignore_whitespace: bool,
+	pub detect_moves: bool,
+	pub detect_copies: bool,
+	pub first_parent: bool,
+	pub root: bool,
+	pub staged: bool,
+	pub no_alt_screen: bool,
+}
+
+pub fn usage(program: &str) -> String {
+	let mut usage = format!("usage: {}", program);
+	for option in OPTIONS.iter().filter(|o| !o.hidden) {
+		match option.value {
+			Some(value) => usage.push_str(&format!(" [--{}=<{}>]", option.name, value)),
+			None => usage.push_str(&format!(" [--{}]", option.name)),
+		}
+	}
+	usage.push_str(" <filepath>... [rev]\n");
+	for option in OPTIONS.iter().filter(|o| !o.hidden) {
+		usage.push_str(&format!("\n  --{:<16} {}", option.name, option.help));
+	}
+	usage.push_str("\n\n<filepath> may end in :<line> to start on that line, e.g. src/main.rs:42");
+	usage.push_str("\n[rev] defaults to HEAD; - reads it from stdin");
+	usage.push_str(
+		"\n--porcelain fields, in order: hash, author, email, committer time (unix seconds), path, line number, code",
+	);
+	usage
+}
+
+// `args` excludes the program name. options may be given as `--name=value` or `--name value`
+pub fn parse(args: &[String]) -> Result<Args, String> {
+	let mut parsed = Args::default();
+	let mut positional = vec![];
+	let mut iter = args.iter();
+	while let Some(arg) = iter.next() {
+		if arg == "--" {
+			positional.extend(iter.by_ref().cloned());
+			break;
+		}
+		let Some(option) = arg.strip_prefix("--") else {
+			positional.push(arg.to_owned());
+			continue;
+		};
+		let (name, value) = match option.split_once('=') {
+			Some((name, value)) => (name, Some(value.to_owned())),
+			None => (option, None),
+		};
+		match name {
+			"encoding" => parsed.encoding = Some(option_value(name, value, &mut iter)?),
+			"since" => parsed.since = Some(option_value(name, value, &mut iter)?),
+			"until" => parsed.until = Some(option_value(name, value, &mut iter)?),
+			"abbrev" => {
+				let abbrev = option_value(name, value, &mut iter)?;
+				parsed.abbrev = Some(parse_abbrev(&abbrev).map_err(|e| format!("--abbrev {}", e))?);
+			}
+			"ignore-revs-file" => parsed.ignore_revs_file = Some(option_value(name, value, &mut iter)?),
+			"compare" => parsed.compare = Some(option_value(name, value, &mut iter)?),
+			"show" => parsed.show = Some(option_value(name, value, &mut iter)?),
+			"completions" => parsed.completions = Some(option_value(name, value, &mut iter)?),
+			"version" => parsed.version = true,
+			"debug" => parsed.debug = true,
+			"follow" => parsed.follow = true,
+			"porcelain" => parsed.porcelain = true,
+			"ignore-whitespace" => parsed.ignore_whitespace = true,
+			"detect-moves" => parsed.detect_moves = true,
+			"detect-copies" => parsed.detect_copies = true,
+			"first-parent" => parsed.first_parent = true,
+			"root" => parsed.root = true,
+			"staged" => parsed.staged = true,
+			"no-alt-screen" => parsed.no_alt_screen = true,
+			_ => return Err(format!("unknown option --{}", name)),
+		}
+	}
+
+	// after the first path, the last argument is the rev unless it names a file
+	if positional.len() > 1 && !Path::new(split_line_number(positional.last().unwrap()).0).exists() {
+		parsed.rev = positional.pop();
+	}
+	if parsed.staged && parsed.rev.is_some() {
+		return Err("--staged blames the index, so it doesn't take a [rev]".to_owned());
+	}
+	if positional.is_empty() && parsed.completions.is_none() && !parsed.version && parsed.show.is_none() {
+		// printing completions, the version or a commit doesn't involve a file
+		return Err("missing <filepath>".to_owned());
+	}
+	parsed.paths = positional;
+	Ok(parsed)
+}
+
+fn option_value(name: &str, value: Option<String>, iter: &mut slice::Iter<String>) -> Result<String, String> {
+	match value {
+		Some(value) => Ok(value),
+		None => iter.next().cloned().ok_or(format!("--{} needs a value", name)),
+	}
+}
+
+// `src/main.rs:42`, as grep and compilers print it, is a file and the line to start on. a file whose name really ends
+// in `:42` is left alone, and so is a drive letter like `C:` on windows
+pub fn split_line_number(arg: &str) -> (&str, Option<usize>) {
+	if Path::new(arg).exists() {
+		return (arg, None);
+	}
+	let Some((path, line)) = arg.rsplit_once(':') else {
+		return (arg, None);
+	};
+	let drive_letter = cfg!(windows) && path.len() == 1 && path.bytes().all(|b| b.is_ascii_alphabetic());
+	if path.is_empty() || drive_letter || !line.bytes().all(|b| b.is_ascii_digit()) {
+		return (arg, None);
+	}
+	match line.parse() {
+		Ok(line) => (path, Some(line)),
+		Err(_) => (arg, None),
+	}
+}
+
+// like git's --abbrev, but never shorter than 4 or longer than a whole hash
+pub fn parse_abbrev(value: &str) -> Result<usize, String> {
+	match value.parse() {
+		Ok(n @ 4..=40) => Ok(n),
+		_ => Err(format!("must be a number from 4 to 40, not {}", value)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse, split_line_number, Args};
+
+	fn strings(args: &[&str]) -> Vec<String> {
+		args.iter().map(|s| s.to_string()).collect()
+	}
+
+	#[test]
+	fn positional() {
+		let args = parse(&strings(&["src/main.rs", "HEAD~2"])).unwrap();
+		assert_eq!(
+			args,
+			Args {
+				paths: vec!["src/main.rs".to_owned()],
+				rev: Some("HEAD~2".to_owned()),
+				..Default::default()
+			}
+		);
+		// tests run from the crate root, so these are files and not revs
+		let args = parse(&strings(&["src/main.rs", "src/args.rs"])).unwrap();
+		assert_eq!(args.paths, ["src/main.rs", "src/args.rs"]);
+		assert_eq!(args.rev, None);
+		let args = parse(&strings(&["src/main.rs", "src/args.rs", "v1.0"])).unwrap();
+		assert_eq!(args.paths, ["src/main.rs", "src/args.rs"]);
+		assert_eq!(args.rev.as_deref(), Some("v1.0"));
+	}
+
+	#[test]
+	fn options() {
+		let args = parse(&strings(&["--encoding=latin1", "a.txt"])).unwrap();
+		assert_eq!(args.encoding.as_deref(), Some("latin1"));
+		let args = parse(&strings(&["a.txt", "--encoding", "latin1"])).unwrap();
+		assert_eq!(args.encoding.as_deref(), Some("latin1"));
+		assert_eq!(args.paths, ["a.txt"]);
+		let args = parse(&strings(&["--completions", "zsh"])).unwrap();
+		assert_eq!(args.completions.as_deref(), Some("zsh"));
+		assert!(parse(&strings(&["--version"])).unwrap().version);
+		assert!(parse(&strings(&["--porcelain", "a.txt"])).unwrap().porcelain);
+		assert_eq!(
+			parse(&strings(&["--show=HEAD~"])).unwrap().show.as_deref(),
+			Some("HEAD~")
+		);
+		let args = parse(&strings(&["--compare", "v1.0", "a.txt", "v2.0"])).unwrap();
+		assert_eq!(args.compare.as_deref(), Some("v1.0"));
+		let args = parse(&strings(&["--since=2.weeks.ago", "--until", "2024-01-01", "a.txt"])).unwrap();
+		assert_eq!(args.since.as_deref(), Some("2.weeks.ago"));
+		assert_eq!(args.until.as_deref(), Some("2024-01-01"));
+		assert_eq!(parse(&strings(&["--abbrev=12", "a.txt"])).unwrap().abbrev, Some(12));
+		let args = parse(&strings(&["--first-parent", "--ignore-revs-file", ".revs", "a.txt"])).unwrap();
+		assert!(args.first_parent && !args.detect_moves && !args.root);
+		assert!(parse(&strings(&["--root", "a.txt"])).unwrap().root);
+		assert!(!args.no_alt_screen);
+		assert!(parse(&strings(&["--no-alt-screen", "a.txt"])).unwrap().no_alt_screen);
+		assert_eq!(args.ignore_revs_file.as_deref(), Some(".revs"));
+	}
+
+	#[test]
+	fn line_numbers() {
+		assert_eq!(split_line_number("src/main.rs:42"), ("src/main.rs", Some(42)));
+		assert_eq!(split_line_number("src/main.rs"), ("src/main.rs", None));
+		assert_eq!(split_line_number("a:b:7"), ("a:b", Some(7)));
+		assert_eq!(split_line_number("C:\\src\\main.rs"), ("C:\\src\\main.rs", None));
+		assert_eq!(split_line_number("main.rs:"), ("main.rs:", None));
+		assert_eq!(split_line_number(":42"), (":42", None));
+		let args = parse(&strings(&["src/main.rs:10", "src/args.rs:20"])).unwrap();
+		assert_eq!(args.paths, ["src/main.rs:10", "src/args.rs:20"]);
+		assert_eq!(args.rev, None);
+	}
+
+	#[test]
+	fn errors() {
+		assert!(parse(&strings(&[])).is_err());
+		assert!(parse(&strings(&["--bogus", "a"])).is_err());
+		assert!(parse(&strings(&["a", "--encoding"])).is_err());
+		assert!(parse(&strings(&["--abbrev=3", "a"])).is_err());
+		assert!(parse(&strings(&["--staged", "src/main.rs", "HEAD"])).is_err());
+		assert!(parse(&strings(&["--abbrev=41", "a"])).is_err());
+		assert_eq!(
+			parse(&strings(&["--abbrev=seven", "a"])),
+			Err("--abbrev must be a number from 4 to 40, not seven".to_owned())
+		);
+	}
+}
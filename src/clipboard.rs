@@ -0,0 +1,43 @@
+use std::io::{self, Write};
+
+// asks the terminal to set the clipboard with an OSC 52 escape sequence, which also works over ssh and inside tmux
+// (with `set-clipboard on`). terminals that don't support it ignore it
+pub fn copy(text: &str) -> io::Result<()> {
+	let mut stdout = io::stdout();
+	write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+	stdout.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+	const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+	let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+	for chunk in bytes.chunks(3) {
+		let n = chunk
+			.iter()
+			.enumerate()
+			.fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+		for i in 0..4 {
+			if i <= chunk.len() {
+				out.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]));
+			} else {
+				out.push('=');
+			}
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::base64;
+
+	#[test]
+	fn encode() {
+		assert_eq!(base64(b""), "");
+		assert_eq!(base64(b"f"), "Zg==");
+		assert_eq!(base64(b"fo"), "Zm8=");
+		assert_eq!(base64(b"foo"), "Zm9v");
+		assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+		assert_eq!(base64("José".as_bytes()), "Sm9zw6k=");
+	}
+}
@@ -0,0 +1,101 @@
+use crate::args::OPTIONS;
+
+pub fn script(shell: &str) -> Option<String> {
+	match shell {
+		"bash" => Some(bash()),
+		"zsh" => Some(zsh()),
+		"fish" => Some(fish()),
+		_ => None,
+	}
+}
+
+fn bash() -> String {
+	let words: Vec<String> = OPTIONS
+		.iter()
+		.map(|o| match o.value {
+			Some(_) => format!("--{}=", o.name),
+			None => format!("--{}", o.name),
+		})
+		.collect();
+	let value_options: Vec<String> = OPTIONS
+		.iter()
+		.filter(|o| o.value.is_some() && o.name != "completions")
+		.map(|o| format!("--{}", o.name))
+		.collect();
+	format!(
+		r#"# bash completion for git-whence
+# install with
+#   git-whence --completions bash > ~/.local/share/bash-completion/completions/git-whence
+# or add `eval "$(git-whence --completions bash)"` to ~/.bashrc
+
+_git_whence() {{
+	local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+	case "$prev" in
+		--completions)
+			COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur"))
+			return
+			;;
+		{value_options})
+			return
+			;;
+	esac
+	if [[ "$cur" == -* ]]; then
+		compopt -o nospace
+		COMPREPLY=($(compgen -W "{words}" -- "$cur"))
+	else
+		COMPREPLY=($(compgen -f -- "$cur"))
+	fi
+}}
+complete -o filenames -F _git_whence git-whence
+"#,
+		value_options = value_options.join("|"),
+		words = words.join(" "),
+	)
+}
+
+fn zsh() -> String {
+	let mut specs = String::new();
+	for option in OPTIONS {
+		let help = option
+			.help
+			.replace('\'', "'\\''")
+			.replace('[', "\\[")
+			.replace(']', "\\]");
+		let value = match (option.name, option.value) {
+			("completions", _) => ":shell:(bash zsh fish)".to_owned(),
+			(_, Some(value)) => format!(":{}:", value),
+			(_, None) => String::new(),
+		};
+		let equals = if option.value.is_some() { "=" } else { "" };
+		specs.push_str(&format!("\t'--{}{}[{}]{}' \\\n", option.name, equals, help, value));
+	}
+	format!(
+		r#"#compdef git-whence
+# zsh completion for git-whence
+# install with
+#   git-whence --completions zsh > "${{fpath[1]}}/_git-whence"
+# then restart zsh (or run `compinit`)
+
+_arguments \
+{specs}	'1:file:_files' \
+	'2:revision:'
+"#
+	)
+}
+
+fn fish() -> String {
+	let mut script = String::from(
+		"# fish completion for git-whence\n# install with\n#   git-whence --completions fish > ~/.config/fish/completions/git-whence.fish\n\n",
+	);
+	for option in OPTIONS {
+		let mut line = format!("complete -c git-whence -l {}", option.name);
+		if option.name == "completions" {
+			line.push_str(" -x -a 'bash zsh fish'");
+		} else if option.value.is_some() {
+			line.push_str(" -x");
+		}
+		line.push_str(&format!(" -d '{}'\n", option.help.replace('\'', "\\'")));
+		script.push_str(&line);
+	}
+	script
+}
@@ -0,0 +1,316 @@
+use git2::Repository;
+use git_whence::{
+	forge::Templates,
+	git::{Column, TimeZone},
+};
+use tui::style::{Color, Modifier, Style};
+
+// settings read from the `whence` section of git config, e.g. `git config --global whence.fullHash true`
+pub struct Config {
+	pub full_hash: bool,
+	pub abbrev: usize,               // hash width when not showing full hashes
+	pub author_width: Option<usize>, // None sizes the column to the longest author
+	pub time_width: usize,
+	pub summary_width: usize,
+	// `whence.columns`: the columns to show, in order. None for the usual hash, author, time, line number and code
+	pub columns: Option<Vec<Column>>,
+	pub encoding: Option<String>,
+	pub quote_path: bool, // false adds `-c core.quotepath=false` so non-ASCII paths come through unescaped
+	// defaults for the blame flags, which the command line can turn on and the settings menu can toggle
+	pub ignore_whitespace: bool,
+	pub detect_moves: bool,
+	pub detect_copies: bool,
+	pub first_parent: bool,
+	pub root: bool, // git's own blame.showRoot
+	pub ignore_revs_file: Option<String>,
+	pub mark_blank_lines: bool,
+	pub url_templates: Templates,
+	pub author_initials: bool,
+	pub right_align_author: bool, // `whence.authorAlign = right`
+	pub scroll: ScrollAmount,     // how far `d` and `u` move
+	pub human_dates: bool,        // `whence.date = human`
+	pub human_date_days: u64,
+	pub time_zone: TimeZone,
+	pub recent_days: Option<u64>, // commits older than this get a dim time column
+	pub scrollbar: bool,
+	pub search_wrap: bool,    // whether `n` and `N` continue from the other end of the file
+	pub mouse: bool,          // capture the mouse to drag the divider next to the panel
+	pub alt_screen: bool,     // false draws on the normal screen, so the last view stays in the scrollback
+	pub refresh: Option<u64>, // seconds between redraws that update relative times, None to only redraw on input
+	pub selection: Style,     // how the selected line stands out
+	pub remember_rev: bool,   // keep where each file was last blamed, to offer it next time
+	pub hyperlinks: bool,     // make commit hashes clickable links to the forge (OSC 8)
+	pub mark_merges: bool,    // end the hashes of merge commits with ᴹ
+	pub footer: bool,         // details of the selected line under the blame
+	pub panel_wrap: bool,     // break long lines in the panel instead of cutting them off
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollAmount {
+	Lines(u16),
+	Percent(u16), // of the window height
+}
+
+impl ScrollAmount {
+	// "10" is 10 lines, "50%" is half the window
+	fn parse(value: &str) -> Option<ScrollAmount> {
+		match value.strip_suffix('%') {
+			Some(percent) => percent
+				.trim()
+				.parse()
+				.ok()
+				.filter(|percent| (1..=100).contains(percent))
+				.map(ScrollAmount::Percent),
+			None => value
+				.trim()
+				.parse()
+				.ok()
+				.filter(|&lines| lines > 0)
+				.map(ScrollAmount::Lines),
+		}
+	}
+
+	pub fn lines(self, height: u16) -> u16 {
+		match self {
+			ScrollAmount::Lines(lines) => lines,
+			ScrollAmount::Percent(percent) => (u32::from(height) * u32::from(percent) / 100) as u16,
+		}
+		.max(1)
+	}
+}
+
+// the settings when git config has none of its own
+impl Default for Config {
+	fn default() -> Config {
+		Config {
+			full_hash: false,
+			abbrev: 8,
+			author_width: Some(12),
+			time_width: 13,
+			summary_width: 30,
+			columns: None,
+			encoding: None,
+			quote_path: true,
+			ignore_whitespace: false,
+			detect_moves: false,
+			detect_copies: false,
+			first_parent: false,
+			root: false,
+			ignore_revs_file: None,
+			author_initials: false,
+			right_align_author: false,
+			scroll: ScrollAmount::Percent(50),
+			search_wrap: true,
+			scrollbar: true,
+			mouse: false,
+			alt_screen: true,
+			refresh: None,
+			selection: Style::default().add_modifier(Modifier::REVERSED),
+			remember_rev: false,
+			hyperlinks: false,
+			url_templates: Templates::default(),
+			mark_merges: false,
+			mark_blank_lines: false,
+			footer: false,
+			panel_wrap: false,
+			human_dates: false,
+			human_date_days: 30,
+			time_zone: TimeZone::Local,
+			recent_days: None,
+		}
+	}
+}
+
+impl Config {
+	pub fn load(repo: &Repository) -> Config {
+		let mut config = Config::default();
+		let git_config = match repo.config() {
+			Ok(git_config) => git_config,
+			Err(_) => return config,
+		};
+		if let Ok(full_hash) = git_config.get_bool("whence.fullHash") {
+			config.full_hash = full_hash;
+		}
+		if let Ok(abbrev) = git_config.get_string("whence.abbrev") {
+			match crate::args::parse_abbrev(&abbrev) {
+				Ok(abbrev) => config.abbrev = abbrev,
+				Err(err) => log::warn!("whence.abbrev {}", err),
+			}
+		}
+		if let Ok(author_width) = git_config.get_string("whence.authorWidth") {
+			if author_width == "auto" {
+				config.author_width = None;
+			} else if let Ok(width) = author_width.parse() {
+				config.author_width = Some(width);
+			}
+		}
+		if let Ok(time_width) = git_config.get_i64("whence.timeWidth") {
+			config.time_width = usize::try_from(time_width).unwrap_or(config.time_width);
+		}
+		if let Ok(summary_width) = git_config.get_i64("whence.summaryWidth") {
+			config.summary_width = usize::try_from(summary_width).unwrap_or(config.summary_width);
+		}
+		if let Ok(columns) = git_config.get_string("whence.columns") {
+			match Column::parse_list(&columns) {
+				Ok(columns) => config.columns = Some(columns),
+				Err(err) => log::warn!("whence.columns: {}", err),
+			}
+		}
+		if let Ok(encoding) = git_config.get_string("whence.encoding") {
+			config.encoding = Some(encoding);
+		}
+		if let Ok(quote_path) = git_config.get_bool("whence.quotePath") {
+			config.quote_path = quote_path;
+		}
+		if let Ok(ignore_whitespace) = git_config.get_bool("whence.ignoreWhitespace") {
+			config.ignore_whitespace = ignore_whitespace;
+		}
+		if let Ok(detect_moves) = git_config.get_bool("whence.detectMoves") {
+			config.detect_moves = detect_moves;
+		}
+		if let Ok(detect_copies) = git_config.get_bool("whence.detectCopies") {
+			config.detect_copies = detect_copies;
+		}
+		if let Ok(first_parent) = git_config.get_bool("whence.firstParent") {
+			config.first_parent = first_parent;
+		}
+		if let Ok(root) = git_config.get_bool("blame.showRoot") {
+			config.root = root;
+		}
+		if let Ok(file) = git_config.get_string("whence.ignoreRevsFile") {
+			config.ignore_revs_file = Some(file);
+		}
+		if let Ok(author_initials) = git_config.get_bool("whence.authorInitials") {
+			config.author_initials = author_initials;
+		}
+		if let Ok(align) = git_config.get_string("whence.authorAlign") {
+			config.right_align_author = align == "right";
+		}
+		if let Ok(scroll) = git_config.get_string("whence.scroll") {
+			config.scroll = ScrollAmount::parse(&scroll).unwrap_or(config.scroll);
+		}
+		if let Ok(date) = git_config.get_string("whence.date") {
+			config.human_dates = date == "human";
+		}
+		if let Ok(days) = git_config.get_i64("whence.humanDateDays") {
+			config.human_date_days = u64::try_from(days).unwrap_or(config.human_date_days);
+		}
+		if let Ok(time_zone) = git_config.get_string("whence.timeZone") {
+			config.time_zone = TimeZone::parse(&time_zone).unwrap_or(config.time_zone);
+		}
+		if let Ok(days) = git_config.get_i64("whence.recentDays") {
+			config.recent_days = u64::try_from(days).ok().filter(|&days| days > 0);
+		}
+		if let Ok(scrollbar) = git_config.get_bool("whence.scrollbar") {
+			config.scrollbar = scrollbar;
+		}
+		if let Ok(search_wrap) = git_config.get_bool("whence.searchWrap") {
+			config.search_wrap = search_wrap;
+		}
+		if let Ok(mouse) = git_config.get_bool("whence.mouse") {
+			config.mouse = mouse;
+		}
+		if let Ok(alt_screen) = git_config.get_bool("whence.altScreen") {
+			config.alt_screen = alt_screen;
+		}
+		if let Ok(refresh) = git_config.get_i64("whence.refresh") {
+			config.refresh = u64::try_from(refresh).ok().filter(|&seconds| seconds > 0);
+		}
+		if let Ok(panel_wrap) = git_config.get_bool("whence.panelWrap") {
+			config.panel_wrap = panel_wrap;
+		}
+		if let Ok(footer) = git_config.get_bool("whence.footer") {
+			config.footer = footer;
+		}
+		if let Ok(mark_merges) = git_config.get_bool("whence.markMerges") {
+			config.mark_merges = mark_merges;
+		}
+		if let Ok(mark_blank_lines) = git_config.get_bool("whence.markBlankLines") {
+			config.mark_blank_lines = mark_blank_lines;
+		}
+		if let Ok(hyperlinks) = git_config.get_bool("whence.hyperlinks") {
+			config.hyperlinks = hyperlinks;
+		}
+		if let Ok(template) = git_config.get_string("whence.commitUrl") {
+			config.url_templates.commit = Some(template);
+		}
+		if let Ok(template) = git_config.get_string("whence.permalinkUrl") {
+			config.url_templates.permalink = Some(template);
+		}
+		if let Ok(remember_rev) = git_config.get_bool("whence.rememberRev") {
+			config.remember_rev = remember_rev;
+		}
+		if let Ok(selection) = git_config.get_string("whence.selection") {
+			config.selection = parse_style(&selection).unwrap_or(config.selection);
+		}
+		config
+	}
+}
+
+// space-separated attributes and a background color, e.g. "bold 237" or "reverse"
+fn parse_style(value: &str) -> Option<Style> {
+	let mut style = Style::default();
+	for word in value.split_whitespace() {
+		style = match word {
+			"reverse" => style.add_modifier(Modifier::REVERSED),
+			"bold" => style.add_modifier(Modifier::BOLD),
+			"underline" => style.add_modifier(Modifier::UNDERLINED),
+			"italic" => style.add_modifier(Modifier::ITALIC),
+			color => style.bg(color.parse::<Color>().ok()?),
+		};
+	}
+	(style != Style::default()).then_some(style)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_style, Config, ScrollAmount};
+	use crate::{temp_repo::TempRepo, terminal::App};
+	use git_whence::git::BlameOptions;
+	use tui::style::{Color, Modifier, Style};
+
+	#[test]
+	fn scroll_amount() {
+		assert_eq!(ScrollAmount::parse("10"), Some(ScrollAmount::Lines(10)));
+		assert_eq!(ScrollAmount::parse("33%"), Some(ScrollAmount::Percent(33)));
+		assert_eq!(ScrollAmount::parse("0"), None);
+		assert_eq!(ScrollAmount::parse("150%"), None);
+		assert_eq!(ScrollAmount::parse("half"), None);
+		assert_eq!(ScrollAmount::Percent(50).lines(41), 20);
+		assert_eq!(ScrollAmount::Percent(1).lines(10), 1);
+		assert_eq!(ScrollAmount::Lines(7).lines(41), 7);
+	}
+
+	#[test]
+	fn style() {
+		assert_eq!(
+			parse_style("reverse"),
+			Some(Style::default().add_modifier(Modifier::REVERSED))
+		);
+		assert_eq!(
+			parse_style("bold 237"),
+			Some(Style::default().add_modifier(Modifier::BOLD).bg(Color::Indexed(237)))
+		);
+		assert_eq!(
+			parse_style("#303030"),
+			Some(Style::default().bg(Color::Rgb(48, 48, 48)))
+		);
+		assert_eq!(parse_style("bold sparkly"), None);
+		assert_eq!(parse_style(""), None);
+	}
+
+	#[test]
+	fn recent_days() {
+		let temp = TempRepo::new("config");
+		let recent_days = |days| {
+			temp.repo.config().unwrap().set_i64("whence.recentDays", days).unwrap();
+			Config::load(&temp.repo).recent_days
+		};
+		assert_eq!(recent_days(7), Some(7));
+		assert_eq!(recent_days(0), None);
+		assert_eq!(recent_days(-1), None);
+		assert_eq!(recent_days(i64::MAX), Some(i64::MAX as u64));
+		// far too many seconds for a Duration, which shouldn't stop the app from starting
+		App::new(&temp.repo, &Config::load(&temp.repo), BlameOptions::default());
+	}
+}
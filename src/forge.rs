@@ -0,0 +1,221 @@
+//! links to code on the forge a repository is hosted on, worked out from its remote url
+
+/// the hosting sites whose url layouts are known
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forge {
+	GitHub,
+	GitLab,
+	Bitbucket,
+	Gitea, // also Forgejo and Codeberg
+	SourceHut,
+}
+
+/// a repository's web address, e.g. `https://github.com/raylu/git-whence`, and which forge serves it
+#[derive(Debug, PartialEq, Eq)]
+pub struct Remote {
+	pub forge: Forge,
+	pub base_url: String,
+	pub templates: Templates,
+}
+
+/// url layouts that replace the forge's own, for self-hosted or unusual forges. `{host}`, `{owner}`, `{repo}`,
+/// `{commit}`, `{path}` and `{line}` are filled in, e.g. `https://{host}/{owner}/{repo}/src/{commit}/{path}#{line}`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Templates {
+	pub commit: Option<String>,
+	pub permalink: Option<String>,
+}
+
+impl Remote {
+	/// parses an https or ssh remote url (`git@host:owner/repo.git`, `ssh://git@host/owner/repo`,
+	/// `https://host/owner/repo.git`). unknown hosts are assumed to lay out urls like GitHub
+	pub fn parse(url: &str) -> Option<Remote> {
+		let (host, path) = if let Some(rest) = url.strip_prefix("https://").or(url.strip_prefix("http://")) {
+			rest.split_once('/')?
+		} else if let Some(rest) = url.strip_prefix("ssh://") {
+			let (host, path) = rest.split_once('/')?;
+			// drop the port, which is for ssh and not the website
+			(host.split(':').next()?, path)
+		} else {
+			// scp-like: [user@]host:path
+			let (host, path) = url.split_once(':')?;
+			if host.contains('/') {
+				return None; // a local path
+			}
+			(host, path)
+		};
+		let host = host.rsplit('@').next()?;
+		let path = path.trim_matches('/').trim_end_matches(".git");
+		if host.is_empty() || path.is_empty() {
+			return None;
+		}
+		let forge = if host.contains("github") {
+			Forge::GitHub
+		} else if host.contains("gitlab") {
+			Forge::GitLab
+		} else if host.contains("bitbucket") {
+			Forge::Bitbucket
+		} else if host.contains("codeberg") || host.contains("gitea") || host.contains("forgejo") {
+			Forge::Gitea
+		} else if host.ends_with("sr.ht") {
+			Forge::SourceHut
+		} else {
+			Forge::GitHub
+		};
+		Some(Remote {
+			forge,
+			base_url: format!("https://{}/{}", host, path),
+			templates: Templates::default(),
+		})
+	}
+
+	/// uses `templates` for the urls they're set for
+	pub fn with_templates(self, templates: Templates) -> Remote {
+		Remote { templates, ..self }
+	}
+
+	/// a link to the page for `commit`, with its message and diff
+	pub fn commit_url(&self, commit: &str) -> String {
+		if let Some(template) = &self.templates.commit {
+			return self.fill(template, commit, "", None);
+		}
+		let base = &self.base_url;
+		match self.forge {
+			Forge::GitHub | Forge::Gitea | Forge::SourceHut => format!("{}/commit/{}", base, commit),
+			Forge::GitLab => format!("{}/-/commit/{}", base, commit),
+			Forge::Bitbucket => format!("{}/commits/{}", base, commit),
+		}
+	}
+
+	/// a link to `line` of `path` as of `commit`, which stays put when the branch moves on
+	pub fn permalink(&self, commit: &str, path: &str, line: usize) -> String {
+		let path = &encode_path(path);
+		if let Some(template) = &self.templates.permalink {
+			return self.fill(template, commit, path, Some(line));
+		}
+		let base = &self.base_url;
+		match self.forge {
+			Forge::GitHub => format!("{}/blob/{}/{}#L{}", base, commit, path, line),
+			Forge::GitLab => format!("{}/-/blob/{}/{}#L{}", base, commit, path, line),
+			Forge::Bitbucket => format!("{}/src/{}/{}#lines-{}", base, commit, path, line),
+			Forge::Gitea => format!("{}/src/commit/{}/{}#L{}", base, commit, path, line),
+			Forge::SourceHut => format!("{}/tree/{}/item/{}#L{}", base, commit, path, line),
+		}
+	}
+
+	fn fill(&self, template: &str, commit: &str, path: &str, line: Option<usize>) -> String {
+		let (host, repo_path) = self
+			.base_url
+			.trim_start_matches("https://")
+			.split_once('/')
+			.unwrap_or_default();
+		// the owner is everything before the last component, so it can be nested like GitLab's subgroups
+		let (owner, repo) = repo_path.rsplit_once('/').unwrap_or(("", repo_path));
+		template
+			.replace("{host}", host)
+			.replace("{owner}", owner)
+			.replace("{repo}", repo)
+			.replace("{commit}", commit)
+			.replace("{path}", path)
+			.replace("{line}", &line.map_or(String::new(), |line| line.to_string()))
+	}
+}
+
+// percent-encodes each of `path`'s components so spaces, `#` and `?` don't end the url's path early,
+// keeping the `/` between them
+fn encode_path(path: &str) -> String {
+	let mut encoded = String::with_capacity(path.len());
+	for byte in path.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+				encoded.push(char::from(byte))
+			}
+			_ => encoded.push_str(&format!("%{:02X}", byte)),
+		}
+	}
+	encoded
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Forge, Remote, Templates};
+
+	#[test]
+	fn parse() {
+		let expected = Some(Remote {
+			forge: Forge::GitHub,
+			base_url: "https://github.com/raylu/git-whence".to_owned(),
+			templates: Templates::default(),
+		});
+		assert_eq!(Remote::parse("git@github.com:raylu/git-whence.git"), expected);
+		assert_eq!(Remote::parse("https://github.com/raylu/git-whence"), expected);
+		assert_eq!(Remote::parse("https://raylu@github.com/raylu/git-whence.git"), expected);
+		assert_eq!(Remote::parse("ssh://git@github.com:22/raylu/git-whence.git"), expected);
+
+		let gitlab = Remote::parse("git@gitlab.example.com:group/sub/project.git").unwrap();
+		assert_eq!(gitlab.forge, Forge::GitLab);
+		assert_eq!(gitlab.base_url, "https://gitlab.example.com/group/sub/project");
+		assert_eq!(
+			Remote::parse("git@git.sr.ht:~raylu/repo").unwrap().forge,
+			Forge::SourceHut
+		);
+
+		assert_eq!(Remote::parse("/home/raylu/repo.git"), None);
+		assert_eq!(Remote::parse("../repo"), None);
+	}
+
+	#[test]
+	fn permalink() {
+		let remote = Remote::parse("git@github.com:raylu/git-whence.git").unwrap();
+		assert_eq!(
+			remote.permalink("abc123", "src/git.rs", 42),
+			"https://github.com/raylu/git-whence/blob/abc123/src/git.rs#L42"
+		);
+		let remote = Remote::parse("https://bitbucket.org/raylu/repo.git").unwrap();
+		assert_eq!(
+			remote.permalink("abc123", "src/git.rs", 42),
+			"https://bitbucket.org/raylu/repo/src/abc123/src/git.rs#lines-42"
+		);
+		assert_eq!(
+			remote.permalink("abc123", "dir/a b#c.rs", 42),
+			"https://bitbucket.org/raylu/repo/src/abc123/dir/a%20b%23c.rs#lines-42"
+		);
+	}
+
+	#[test]
+	fn commit_url() {
+		let remote = Remote::parse("git@gitlab.com:raylu/repo.git").unwrap();
+		assert_eq!(
+			remote.commit_url("abc123"),
+			"https://gitlab.com/raylu/repo/-/commit/abc123"
+		);
+	}
+
+	#[test]
+	fn templates() {
+		let remote = Remote::parse("git@git.example.com:team/sub/project.git")
+			.unwrap()
+			.with_templates(Templates {
+				commit: None,
+				permalink: Some("https://{host}/cgit/{owner}/{repo}/tree/{path}?id={commit}#n{line}".to_owned()),
+			});
+		assert_eq!(
+			remote.permalink("abc123", "src/git.rs", 42),
+			"https://git.example.com/cgit/team/sub/project/tree/src/git.rs?id=abc123#n42"
+		);
+		assert_eq!(
+			remote.permalink("abc123", "dir/a b#c.rs", 42),
+			"https://git.example.com/cgit/team/sub/project/tree/dir/a%20b%23c.rs?id=abc123#n42"
+		);
+		// without a template of its own, commits link to the forge's usual page
+		assert_eq!(
+			remote.commit_url("abc123"),
+			"https://git.example.com/team/sub/project/commit/abc123"
+		);
+		let remote = remote.with_templates(Templates {
+			commit: Some("https://{host}/{repo}/c/{commit}".to_owned()),
+			permalink: None,
+		});
+		assert_eq!(remote.commit_url("abc123"), "https://git.example.com/project/c/abc123");
+	}
+}
@@ -0,0 +1,68 @@
+//! fuzzy matching for pickers: a query matches any candidate that contains its characters in order, and matches that
+//! look like what was meant (runs of characters, the start of a path component) rank first
+
+/// scores `candidate` against `query`, case-insensitively, or returns `None` if it doesn't match. higher is better
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+	let mut score = 0;
+	let mut previous: Option<char> = None;
+	let mut matched_previous = false;
+	let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+	for c in candidate.chars() {
+		let Some(&wanted) = query_chars.peek() else {
+			break;
+		};
+		let matched = c.to_lowercase().eq(wanted.to_lowercase());
+		if matched {
+			query_chars.next();
+			score += 1;
+			if matched_previous {
+				score += 5;
+			}
+			if previous.is_none_or(|p| matches!(p, '/' | '_' | '-' | '.' | ' ')) {
+				score += 3;
+			}
+		}
+		matched_previous = matched;
+		previous = Some(c);
+	}
+	if query_chars.peek().is_some() {
+		return None;
+	}
+	// between equally good matches, the shorter path is more likely what was meant
+	Some(score * 1000 - i32::try_from(candidate.len()).unwrap_or(999).min(999))
+}
+
+/// the indices of `candidates` that match `query`, best first
+pub fn filter<S: AsRef<str>>(query: &str, candidates: &[S]) -> Vec<usize> {
+	let mut scored: Vec<(i32, usize)> = candidates
+		.iter()
+		.enumerate()
+		.filter_map(|(i, candidate)| score(query, candidate.as_ref()).map(|score| (score, i)))
+		.collect();
+	// stable, so ties keep the candidates' order
+	scored.sort_by_key(|&(score, _)| -score);
+	scored.into_iter().map(|(_, i)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{filter, score};
+
+	#[test]
+	fn matches_in_order() {
+		assert!(score("gtr", "src/git.rs").is_some());
+		assert!(score("GIT", "src/git.rs").is_some());
+		assert!(score("", "src/git.rs").is_some());
+		assert!(score("rtg", "src/git.rs").is_none());
+		assert!(score("gitz", "src/git.rs").is_none());
+	}
+
+	#[test]
+	fn ranking() {
+		let files = ["src/git_blame_porcelain.rs", "readme.md", "src/git.rs", "src/logger.rs"];
+		assert_eq!(filter("git", &files), [2, 0]);
+		// a run of characters beats the same characters scattered
+		assert_eq!(filter("gr", &files)[0], 2);
+		assert_eq!(filter("readme", &files), [1]);
+	}
+}
@@ -1,107 +1,871 @@
 use ansi_to_tui::IntoText;
 use git2::{DiffLineType, Oid, Repository};
 use std::{
-	error,
+	cell::OnceCell,
+	collections::HashMap,
+	env, error,
+	ffi::{OsStr, OsString},
+	io::{self, Write},
+	iter,
 	path::{Path, PathBuf},
-	process, time, vec,
+	process,
+	rc::Rc,
+	time::{Duration, Instant, SystemTime},
+	vec,
 };
 use tui::{
-	style::{Color, Style},
+	style::{Color, Modifier, Style},
 	text::{Line, Span, Text},
 };
 
+use crate::layout;
+
 #[derive(Debug)]
-pub struct BlameHunk<'a> {
-	pub line: Line<'a>,
+pub struct BlameHunk {
 	pub commit: Oid,
 	pub path: Option<PathBuf>,
+	pub previous: Option<(Oid, PathBuf)>, // where `commit` got the line from: its parent and the path there
+	pub orig_line_num: i32,               // where the line sits in `commit`'s version of the file
+	pub line_num: i32,
+	pub code: String,
+	pub hunk_start: bool, // only the first line of a hunk shows the commit columns
+	pub meta: Rc<CommitMeta>,
 }
 
-pub fn blame<'a>(
-	repo: &'a Repository,
+// per-commit details shared by every line blamed on that commit
+#[derive(Debug)]
+pub struct CommitMeta {
+	pub author: String,
+	pub author_mail: String,
+	pub author_time: SystemTime,
+	pub author_tz: i32, // seconds east of UTC
+	pub committer: String,
+	pub committer_mail: String,
+	pub commit_time: SystemTime,
+	pub commit_tz: i32,
+	pub summary: String,
+	pub is_merge: OnceCell<bool>, // unknown until `find_merges` looks it up
+	pub boundary: bool,           // where blame stopped looking further back, marked with ^ like git does
+}
+
+impl CommitMeta {
+	pub fn is_rewritten(&self) -> bool {
+		self.author_time != self.commit_time
+	}
+}
+
+// how the commit columns in front of the code are laid out
+#[derive(Clone, PartialEq)]
+pub struct Columns {
+	pub hash_width: usize,
+	pub author_width: usize,
+	pub time_width: usize,
+	pub summary_width: usize,
+	pub show_hash: bool,
+	pub show_author: bool,
+	pub show_time: bool,
+	pub show_summary: bool,
+	pub show_line_num: bool,
+	pub order: Vec<Column>, // the order of the columns, including hidden ones. the code is always last
+	pub mark_blank_lines: bool, // a dim `~` in the code column of empty lines
+	pub no_color: bool,     // NO_COLOR is set, so markers that are only there to be subtle go uncolored
+	pub highlight_trailing_whitespace: bool,
+	pub mark_rewritten: bool, // flag commits whose author and commit times differ (rebased, cherry-picked, amended)
+	pub mark_merges: bool,    // flag merge commits, once `find_merges` has looked them up
+	pub author_initials: bool, // a colored chip and initials instead of the full name
+	pub right_align_author: bool,
+	pub human_dates: bool, // like `git log --date=human`: relative times only for commits newer than `human_threshold`
+	pub human_threshold: Duration,
+	pub time_zone: TimeZone,                // for dates
+	pub recent_threshold: Option<Duration>, // times of commits older than this are dimmed so recent ones stand out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+	Hash,
+	Author,
+	Time,
+	Summary,
+	LineNumber,
+	Code,
+}
+
+pub const DEFAULT_COLUMNS: [Column; 6] = [
+	Column::Hash,
+	Column::Author,
+	Column::Time,
+	Column::Summary,
+	Column::LineNumber,
+	Column::Code,
+];
+
+impl Column {
+	fn parse(name: &str) -> Option<Column> {
+		match name {
+			"hash" => Some(Column::Hash),
+			"author" => Some(Column::Author),
+			"time" => Some(Column::Time),
+			"summary" => Some(Column::Summary),
+			"line" => Some(Column::LineNumber),
+			"code" => Some(Column::Code),
+			_ => None,
+		}
+	}
+
+	// a comma-separated list like `line,hash,author,code`: the columns to show, in order. the code can be left
+	// out, but if it's there it has to be last since it takes up the rest of the line
+	pub fn parse_list(list: &str) -> Result<Vec<Column>, String> {
+		let mut columns = vec![];
+		for name in list.split(',').map(str::trim) {
+			let column = Column::parse(name).ok_or_else(|| format!("unknown column: {}", name))?;
+			if columns.contains(&column) {
+				return Err(format!("{} is listed twice", name));
+			}
+			if columns.last() == Some(&Column::Code) {
+				return Err("code has to be the last column".to_owned());
+			}
+			columns.push(column);
+		}
+		if columns.last() != Some(&Column::Code) {
+			columns.push(Column::Code);
+		}
+		Ok(columns)
+	}
+
+	// `listed` in its order, followed by the columns it leaves out (which start hidden) so they can still be
+	// toggled on, and the code
+	pub fn order(listed: &[Column]) -> Vec<Column> {
+		let mut order: Vec<Column> = listed
+			.iter()
+			.copied()
+			.filter(|&column| column != Column::Code)
+			.collect();
+		for column in DEFAULT_COLUMNS {
+			if !order.contains(&column) {
+				order.push(column);
+			}
+		}
+		order
+	}
+}
+
+// which timezone dates are shown in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeZone {
+	Local,
+	Utc,
+	Committer, // the one each commit was made in, like `git log --date=default`. author times use the author's
+}
+
+impl TimeZone {
+	// `whence.timeZone`: local, utc or committer
+	pub fn parse(name: &str) -> Option<TimeZone> {
+		match name {
+			"local" => Some(TimeZone::Local),
+			"utc" => Some(TimeZone::Utc),
+			"committer" => Some(TimeZone::Committer),
+			_ => None,
+		}
+	}
+
+	pub fn next(self) -> TimeZone {
+		match self {
+			TimeZone::Local => TimeZone::Utc,
+			TimeZone::Utc => TimeZone::Committer,
+			TimeZone::Committer => TimeZone::Local,
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			TimeZone::Local => "local time",
+			TimeZone::Utc => "UTC",
+			TimeZone::Committer => "the committer's timezone",
+		}
+	}
+
+	// `time` in this timezone. `offset` is the one it was recorded with (seconds east of UTC), for `Committer`
+	pub fn date(self, time: SystemTime, offset: i32) -> chrono::DateTime<chrono::FixedOffset> {
+		let utc = chrono::DateTime::<chrono::Utc>::from(time);
+		match self {
+			TimeZone::Local => utc.with_timezone(&chrono::Local).fixed_offset(),
+			TimeZone::Utc => utc.fixed_offset(),
+			TimeZone::Committer => match chrono::FixedOffset::east_opt(offset) {
+				Some(offset) => utc.with_timezone(&offset),
+				None => utc.fixed_offset(),
+			},
+		}
+	}
+}
+
+// "■ ABC": the chip, a space and up to 3 initials
+const INITIALS_WIDTH: usize = 5;
+
+impl Columns {
+	// width of a commit column, which continuation lines fill with spaces. None for hidden columns and the others
+	fn column_width(&self, column: Column) -> Option<usize> {
+		let (show, width) = match column {
+			Column::Hash => (self.show_hash, self.hash_width),
+			Column::Author => (self.show_author, self.author_column_width()),
+			Column::Time => (self.show_time, self.time_width),
+			Column::Summary => (self.show_summary, self.summary_width),
+			Column::LineNumber | Column::Code => (false, 0),
+		};
+		show.then_some(width)
+	}
+
+	// where the hash starts on the line for `line_num`, or None if it's hidden
+	pub fn hash_offset(&self, line_num: i32) -> Option<usize> {
+		if !self.show_hash {
+			return None;
+		}
+		let mut offset = 0;
+		let mut after_column = false;
+		for &column in &self.order {
+			if column == Column::Hash {
+				return Some(offset + usize::from(after_column));
+			} else if column == Column::LineNumber && self.show_line_num {
+				offset += layout::line_number(line_num).len();
+				after_column = false;
+			} else if let Some(width) = self.column_width(column) {
+				offset += width + usize::from(after_column);
+				after_column = true;
+			}
+		}
+		None
+	}
+
+	fn author_column_width(&self) -> usize {
+		if self.author_initials {
+			INITIALS_WIDTH
+		} else {
+			self.author_width
+		}
+	}
+}
+
+// flags passed through to `git blame`
+#[derive(Default)]
+pub struct BlameOptions {
+	pub encoding: Option<String>,
+	pub quote_path: bool,
+	pub since: Option<String>, // lines older than this are blamed on the oldest commit after it, the boundary
+	pub ignore_whitespace: bool, // -w
+	pub detect_moves: bool,    // -M: lines moved within the file keep their original commit
+	pub detect_copies: bool,   // -C: and so do lines moved or copied from other files in the same commit
+	pub first_parent: bool,    // only follow the first parent of merges
+	pub root: bool,            // blame lines from the root commit on it like any other instead of as a boundary
+	pub ignore_revs_file: Option<String>, // relative to the top of the working tree
+	pub ignore_revs: Vec<Oid>, // skipped like the commits in `ignore_revs_file`
+}
+
+// blaming at the null commit (`Oid::zero()`) blames the staged version of the file, what's in the index, on top of
+// HEAD. lines that are only staged are blamed on the null commit too, by "Not Committed Yet"
+pub fn blame(
+	repo: &Repository,
 	rel_path: &Path,
 	start_commit: Oid,
-) -> Result<Vec<BlameHunk<'a>>, Box<dyn error::Error>> {
-	let output = process::Command::new("git")
-		.args([
-			"blame",
-			"--porcelain",
-			rel_path.to_str().unwrap(),
-			&start_commit.to_string(),
-		])
-		.current_dir(repo.path())
-		.output()?;
-	if !output.status.success() {
-		return Err(std::str::from_utf8(&output.stderr)?.into());
+	options: &BlameOptions,
+) -> Result<Vec<BlameHunk>, Box<dyn error::Error>> {
+	let (blame, warning) = blame_with_warnings(repo, rel_path, start_commit, options)?;
+	if let Some(warning) = warning {
+		log::warn!("git blame: {}", warning);
+	}
+	Ok(blame)
+}
+
+// like `blame`, but also returns anything git printed to stderr while succeeding, like warnings about
+// --ignore-revs-file, for showing to the user
+pub fn blame_with_warnings(
+	repo: &Repository,
+	rel_path: &Path,
+	start_commit: Oid,
+	options: &BlameOptions,
+) -> Result<(Vec<BlameHunk>, Option<String>), Box<dyn error::Error>> {
+	if is_binary(repo, rel_path, start_commit) {
+		return Err(format!("{} is a binary file; cannot blame", rel_path.display()).into());
 	}
-	let blame_output = std::str::from_utf8(&output.stdout)?;
-	let blame = crate::git_blame_porcelain::parse_blame_porcelain(blame_output)?;
+	// run from the top of the working tree rather than the .git dir so git finds the .mailmap there
+	let mut command = blame_command(repo.workdir().unwrap_or(repo.path()), rel_path, start_commit, options);
+	let started = Instant::now();
+	let output = if start_commit.is_zero() {
+		let staged = blob_at(repo, rel_path, start_commit)?;
+		output_with_stdin(&mut command, staged.content())?
+	} else {
+		command.output()?
+	};
+	log::debug!("ran {:?} in {:?}", command, started.elapsed());
+	let warning = check_output(&output).inspect_err(|stderr| {
+		log::error!("git blame failed: {}", stderr);
+	})?;
+	// code in another encoding shouldn't make the whole blame fail
+	let blame_output = String::from_utf8_lossy(&output.stdout);
+	let started = Instant::now();
+	let blame = crate::git_blame_porcelain::parse_blame_porcelain(&blame_output).inspect_err(|e| {
+		log::error!("couldn't parse blame output: {}", e);
+	})?;
+	log::debug!("parsed {} hunks in {:?}", blame.len(), started.elapsed());
 
 	let mut out = vec![];
-	let now = time::SystemTime::now();
-	let duration_formatter = timeago::Formatter::new();
+	let mut metas: HashMap<&str, Rc<CommitMeta>> = HashMap::new();
 	for b in blame {
-		let commit_time = b.info.commit_time;
-		let time_display = duration_formatter.convert(now.duration_since(commit_time).unwrap_or_default());
-		let mut spans = vec![
-			Span::styled(format!("{:.8}", b.commit), Style::default().fg(Color::Yellow)),
-			Span::raw(format!(" {}", fmt_width(b.info.author, 12))),
-			Span::styled(
-				format!(" {}", fmt_width(&time_display, 13)),
-				Style::default().fg(Color::LightRed),
-			),
-		];
-		spans.append(&mut format_line_num_and_code(b.line_num, b.code[0]));
-		let line_path = b.info.path;
-		out.push(BlameHunk {
-			line: Line::from(spans),
-			commit: Oid::from_str(b.commit)?,
-			path: line_path.map(|p| p.to_owned()),
+		let meta = metas.entry(b.commit).or_insert_with(|| {
+			Rc::new(CommitMeta {
+				author: b.info.author.to_owned(),
+				author_mail: b.info.author_mail.to_owned(),
+				author_time: b.info.author_time,
+				author_tz: b.info.author_tz,
+				committer: b.info.committer.to_owned(),
+				committer_mail: b.info.committer_mail.to_owned(),
+				commit_time: b.info.commit_time,
+				commit_tz: b.info.commit_tz,
+				summary: b.info.summary.to_owned(),
+				is_merge: OnceCell::new(),
+				boundary: b.info.boundary,
+			})
 		});
-
-		for i in 1..b.code.len() {
-			let mut spans = vec![Span::raw(" ".repeat(35))];
-			let line_num = b.line_num + i32::try_from(i).unwrap();
-			spans.append(&mut format_line_num_and_code(line_num, b.code[i]));
+		let commit = Oid::from_str(b.commit)?;
+		let previous = match b.info.previous {
+			Some((previous, path)) => Some((Oid::from_str(previous)?, path.to_owned())),
+			None => None,
+		};
+		for (i, code) in b.code.iter().enumerate() {
 			out.push(BlameHunk {
-				line: Line::from(spans),
-				commit: Oid::from_str(b.commit)?,
-				path: line_path.map(|p| p.to_owned()),
+				commit,
+				path: b.info.path.map(Path::to_owned),
+				previous: previous.clone(),
+				orig_line_num: b.orig_line_num + i32::try_from(i).unwrap(),
+				line_num: b.line_num + i32::try_from(i).unwrap(),
+				code: (*code).to_owned(),
+				hunk_start: i == 0,
+				meta: meta.clone(),
 			});
 		}
 	}
-	Ok(out)
+	Ok((out, warning))
 }
 
-fn fmt_width(s: &str, width: usize) -> String {
-	let mut out = String::new();
-	match s.char_indices().nth(width) {
-		None => out.push_str(s),
-		Some((i, _)) => out.push_str(&s[..i]),
+// the commit and path to reblame at to see `hunk`'s line before its commit. git names them with `previous`, which
+// follows renames and picks the right parent of a merge. lines it didn't name one for are looked for in the first
+// parent under the same path
+pub fn blame_parent(repo: &Repository, hunk: &BlameHunk, current_path: &Path) -> Result<(Oid, PathBuf), git2::Error> {
+	if let Some((commit, path)) = &hunk.previous {
+		return Ok((*commit, path.clone()));
+	}
+	let parent = repo.find_commit(hunk.commit)?.parent_id(0)?;
+	Ok((parent, hunk.path.clone().unwrap_or_else(|| current_path.to_owned())))
+}
+
+// `git blame --contents -` reads the staged file from stdin. git reads all of it before writing anything, so
+// writing it up front can't deadlock
+fn output_with_stdin(command: &mut process::Command, input: &[u8]) -> io::Result<process::Output> {
+	let mut child = command
+		.stdin(process::Stdio::piped())
+		.stdout(process::Stdio::piped())
+		.stderr(process::Stdio::piped())
+		.spawn()?;
+	// dropped right away, closing stdin
+	child.stdin.take().unwrap().write_all(input)?;
+	child.wait_with_output()
+}
+
+// git's stderr: an error if it failed, otherwise any warnings it printed. only stdout is ever parsed
+fn check_output(output: &process::Output) -> Result<Option<String>, String> {
+	let stderr = String::from_utf8_lossy(&output.stderr).trim_end().to_owned();
+	if !output.status.success() {
+		return Err(stderr);
+	}
+	Ok(Some(stderr).filter(|stderr| !stderr.is_empty()))
+}
+
+// libgit2's check, like git's: a NUL byte near the start of the blob
+fn is_binary(repo: &Repository, rel_path: &Path, commit: Oid) -> bool {
+	blob_at(repo, rel_path, commit).is_ok_and(|blob| blob.is_binary())
+}
+
+// the file at `commit`, or in the index for the null commit
+fn blob_at<'r>(repo: &'r Repository, rel_path: &Path, commit: Oid) -> Result<git2::Blob<'r>, git2::Error> {
+	if commit.is_zero() {
+		let entry = repo
+			.index()?
+			.get_path(rel_path, 0)
+			.ok_or_else(|| git2::Error::from_str(&format!("{} isn't staged", rel_path.display())))?;
+		return repo.find_blob(entry.id);
+	}
+	repo.find_commit(commit)?
+		.tree()?
+		.get_path(rel_path)?
+		.to_object(repo)?
+		.peel_to_blob()
+}
+
+// whether the index has a version of `rel_path` that HEAD doesn't
+pub fn has_staged_changes(repo: &Repository, rel_path: &Path) -> bool {
+	let Some(staged) = repo.index().ok().and_then(|index| index.get_path(rel_path, 0)) else {
+		return false;
 	};
-	if out.len() < width {
-		out.push_str(&" ".repeat(width - out.len()));
+	let head = repo
+		.head()
+		.and_then(|head| head.peel_to_tree())
+		.and_then(|tree| tree.get_path(rel_path));
+	!head.is_ok_and(|entry| entry.id() == staged.id)
+}
+
+fn blame_command(dir: &Path, rel_path: &Path, start_commit: Oid, options: &BlameOptions) -> process::Command {
+	let mut command = process::Command::new("git");
+	if !options.quote_path {
+		command.args(["-c", "core.quotepath=false"]);
 	}
-	out
+	if !options.root {
+		// `root` starts out as blame.showRoot, so this only matters once it's been turned off in the settings
+		command.args(["-c", "blame.showRoot=false"]);
+	}
+	command.args(["blame", "--porcelain"]);
+	if let Some(encoding) = &options.encoding {
+		command.arg(format!("--encoding={}", encoding));
+	}
+	if let Some(since) = &options.since {
+		command.arg(format!("--since={}", since));
+	}
+	if options.ignore_whitespace {
+		command.arg("-w");
+	}
+	if options.detect_moves {
+		command.arg("-M");
+	}
+	if options.detect_copies {
+		command.arg("-C");
+	}
+	if options.first_parent {
+		command.arg("--first-parent");
+	}
+	if options.root {
+		command.arg("--root");
+	}
+	if let Some(file) = &options.ignore_revs_file {
+		command.arg(format!("--ignore-revs-file={}", file));
+	}
+	for rev in &options.ignore_revs {
+		command.arg(format!("--ignore-rev={}", rev));
+	}
+	if start_commit.is_zero() {
+		// without a commit, `--contents` starts from HEAD
+		command.args(["--contents", "-"]);
+	} else {
+		command.arg(start_commit.to_string());
+	}
+	// `--` keeps a path like `-foo` from being read as an option
+	command.arg("--").arg(rel_path).current_dir(dir);
+	command
 }
 
-fn format_line_num_and_code(line_num: i32, line: &str) -> Vec<Span<'static>> {
-	vec![
-		Span::styled(format!(" {:4} ", line_num), Style::default().fg(Color::DarkGray)),
-		Span::raw(line.replace('\t', "    ")),
-	]
+// looks up which of the commits in `blame` are merges for `Columns::mark_merges`. each commit is only looked up once
+pub fn find_merges(repo: &Repository, blame: &[BlameHunk]) {
+	for hunk in blame.iter().filter(|hunk| hunk.hunk_start) {
+		hunk.meta.is_merge.get_or_init(|| {
+			repo.find_commit(hunk.commit)
+				.is_ok_and(|commit| commit.parent_count() > 1)
+		});
+	}
 }
 
-pub fn show(repo: &Repository, commit_id: Oid) -> Text<'static> {
-	let commit = match repo.find_commit(commit_id) {
-		Ok(commit) => commit,
-		Err(e) => return Text::raw(e.to_string()),
+pub fn format_blame_line(hunk: &BlameHunk, columns: &Columns, now: SystemTime) -> Line<'static> {
+	let mut spans = vec![];
+	// a commit column was just pushed, so whatever comes next needs a space in between. the line number brings
+	// its own
+	let mut after_column = false;
+	for &column in &columns.order {
+		match column {
+			Column::LineNumber => {
+				if columns.show_line_num {
+					spans.push(line_number_span(hunk.line_num));
+					after_column = false;
+				}
+			}
+			Column::Code => {
+				if after_column {
+					spans.push(Span::raw(" "));
+				}
+				spans.append(&mut format_code(&hunk.code, columns));
+			}
+			_ => {
+				let Some(width) = columns.column_width(column) else {
+					continue;
+				};
+				if after_column {
+					spans.push(Span::raw(" "));
+				}
+				if hunk.hunk_start {
+					spans.append(&mut commit_column(hunk, column, columns, now));
+				} else {
+					spans.push(Span::raw(" ".repeat(width)));
+				}
+				after_column = true;
+			}
+		}
+	}
+	Line::from(spans)
+}
+
+fn commit_column(hunk: &BlameHunk, column: Column, columns: &Columns, now: SystemTime) -> Vec<Span<'static>> {
+	match column {
+		Column::Hash => {
+			let mut hash = hunk.commit.to_string();
+			if hunk.meta.boundary {
+				hash.insert(0, '^');
+			}
+			hash.truncate(columns.hash_width);
+			if columns.mark_merges && hunk.meta.is_merge.get() == Some(&true) {
+				// the marker takes the column's last character
+				hash.pop();
+				hash.push('ᴹ');
+			}
+			// staged lines have no commit yet
+			let color = if hunk.commit.is_zero() {
+				Color::DarkGray
+			} else {
+				Color::Yellow
+			};
+			vec![Span::styled(hash, Style::default().fg(color))]
+		}
+		Column::Author if columns.author_initials => {
+			let color = author_color(&hunk.meta.author);
+			vec![Span::styled(
+				format!(
+					"■ {}",
+					layout::fit_width(&initials(&hunk.meta.author), INITIALS_WIDTH - 2)
+				),
+				Style::default().fg(color),
+			)]
+		}
+		Column::Author => {
+			let author = if columns.right_align_author {
+				layout::fit_width_right(&hunk.meta.author, columns.author_width)
+			} else {
+				layout::fit_width(&hunk.meta.author, columns.author_width)
+			};
+			vec![Span::raw(author)]
+		}
+		Column::Time => {
+			let time_display = format_time(hunk.meta.commit_time, hunk.meta.commit_tz, now, columns);
+			let time_column = if columns.mark_rewritten && hunk.meta.is_rewritten() {
+				// the marker takes the column's last character
+				let mut column = layout::fit_width(&time_display, columns.time_width.saturating_sub(1));
+				column.push('*');
+				column
+			} else {
+				layout::fit_width(&time_display, columns.time_width)
+			};
+			let age = now.duration_since(hunk.meta.commit_time).unwrap_or_default();
+			let color = match columns.recent_threshold {
+				Some(threshold) if age > threshold => Color::DarkGray,
+				_ => Color::LightRed,
+			};
+			vec![Span::styled(time_column, Style::default().fg(color))]
+		}
+		Column::Summary => vec![Span::styled(
+			layout::fit_width(
+				&layout::truncate(&hunk.meta.summary, columns.summary_width),
+				columns.summary_width,
+			),
+			Style::default().fg(Color::DarkGray),
+		)],
+		Column::LineNumber | Column::Code => vec![],
+	}
+}
+
+fn format_time(time: SystemTime, offset: i32, now: SystemTime, columns: &Columns) -> String {
+	let age = now.duration_since(time).unwrap_or_default();
+	if !columns.human_dates || age < columns.human_threshold {
+		return timeago::Formatter::new().convert(age);
+	}
+	use chrono::Datelike;
+	let date = columns.time_zone.date(time, offset);
+	// the year only when it isn't this one
+	if date.year() == columns.time_zone.date(now, offset).year() {
+		date.format("%b %-d").to_string()
+	} else {
+		date.format("%b %-d %Y").to_string()
+	}
+}
+
+// first letter of each part of the name, at most 3: "Ada King Lovelace" is AKL
+fn initials(name: &str) -> String {
+	name.split_whitespace()
+		.filter_map(|part| part.chars().next())
+		.flat_map(char::to_uppercase)
+		.take(INITIALS_WIDTH - 2)
+		.collect()
+}
+
+// hashed from the name so an author gets the same color every time
+fn author_color(name: &str) -> Color {
+	const COLORS: [Color; 10] = [
+		Color::Red,
+		Color::Green,
+		Color::Blue,
+		Color::Magenta,
+		Color::Cyan,
+		Color::LightGreen,
+		Color::LightBlue,
+		Color::LightMagenta,
+		Color::Indexed(208), // orange
+		Color::Indexed(141), // purple
+	];
+	// FNV-1a
+	let hash = name.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+	});
+	COLORS[(hash % COLORS.len() as u64) as usize]
+}
+
+// one line of `--porcelain` output. the fields are tab-separated and their order is part of the interface:
+// hash, author, author email, committer time (unix seconds), path, line number, code.
+// the code comes last so it can keep its own tabs (`cut -f7-`)
+pub fn format_porcelain_line(hunk: &BlameHunk, rel_path: &Path) -> String {
+	let commit_time = hunk
+		.meta
+		.commit_time
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs();
+	format!(
+		"{}\t{}\t{}\t{}\t{}\t{}\t{}",
+		hunk.commit,
+		hunk.meta.author,
+		hunk.meta.author_mail,
+		commit_time,
+		hunk.path.as_deref().unwrap_or(rel_path).display(),
+		hunk.line_num,
+		hunk.code
+	)
+}
+
+pub fn format_file_line(line_num: usize, code: &str, columns: &Columns) -> Line<'static> {
+	Line::from(format_line_num_and_code(
+		i32::try_from(line_num).unwrap_or(i32::MAX),
+		code,
+		columns,
+	))
+}
+
+fn format_line_num_and_code(line_num: i32, line: &str, columns: &Columns) -> Vec<Span<'static>> {
+	let mut spans = vec![];
+	if columns.show_line_num {
+		spans.push(line_number_span(line_num));
+	}
+	spans.append(&mut format_code(line, columns));
+	spans
+}
+
+fn line_number_span(line_num: i32) -> Span<'static> {
+	Span::styled(layout::line_number(line_num), Style::default().fg(Color::DarkGray))
+}
+
+fn format_code(line: &str, columns: &Columns) -> Vec<Span<'static>> {
+	let mut spans = vec![];
+	if line.is_empty() && columns.mark_blank_lines {
+		let style = if columns.no_color {
+			Style::default()
+		} else {
+			Style::default().fg(Color::DarkGray)
+		};
+		spans.push(Span::styled("~", style));
+	} else if columns.highlight_trailing_whitespace {
+		// split after expanding tabs so a trailing tab is highlighted up to its tab stop
+		let line = layout::expand_tabs(line);
+		let (code, trailing) = layout::split_trailing_whitespace(&line);
+		spans.push(Span::raw(code.to_owned()));
+		if !trailing.is_empty() {
+			spans.push(Span::styled(trailing.to_owned(), Style::default().bg(Color::Red)));
+		}
+	} else {
+		spans.push(Span::raw(layout::expand_tabs(line)));
+	}
+	spans
+}
+
+pub fn path_exists(repo: &Repository, rel_path: &Path, commit: Oid) -> bool {
+	if commit.is_zero() {
+		return repo.index().is_ok_and(|index| index.get_path(rel_path, 0).is_some());
+	}
+	repo.find_commit(commit)
+		.and_then(|commit| commit.tree())
+		.and_then(|tree| tree.get_path(rel_path))
+		.is_ok()
+}
+
+// what `rel_path` (as named at HEAD) was called at `commit`, by following its renames back through HEAD's history.
+// `commit` needn't be reachable from HEAD, but renames made only on its side of a fork aren't followed
+pub fn follow_path(repo: &Repository, rel_path: &Path, commit: Oid) -> Result<Option<PathBuf>, Box<dyn error::Error>> {
+	let head = repo.head()?.peel_to_commit()?.id();
+	// where `commit`'s history meets HEAD's. None when they share no history
+	let Ok(base) = repo.merge_base(head, commit) else {
+		return Ok(None);
 	};
-	let diff = match diff_for_commit(repo, &commit) {
-		Ok(diff) => diff,
+	let mut command = process::Command::new("git");
+	command
+		.args(["log", "--follow", "--name-only", "--format=commit %H", "HEAD", "--"])
+		.arg(rel_path)
+		.current_dir(repo.path());
+	let output = command.output()?;
+	log::debug!("ran {:?}", command);
+	if !output.status.success() {
+		return Err(String::from_utf8_lossy(&output.stderr).into());
+	}
+	// newest first, so the first commit that `base` can reach named the file as it was at `base`
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	for (hash, name) in log_names(&stdout) {
+		let touched = Oid::from_str(hash)?;
+		if touched == base || repo.graph_descendant_of(base, touched)? {
+			let name = PathBuf::from(name);
+			return Ok(path_exists(repo, &name, commit).then_some(name));
+		}
+	}
+	Ok(None)
+}
+
+// the commits in `git log --name-only --format='commit %H'` output and the name each one gave the file
+fn log_names(log: &str) -> impl Iterator<Item = (&str, &str)> {
+	let mut lines = log.lines().filter(|line| !line.is_empty()).peekable();
+	iter::from_fn(move || loop {
+		let hash = lines.next()?.strip_prefix("commit ");
+		// merges are listed without a name, so the next line can already be the next commit
+		if let Some(hash) = hash {
+			if let Some(name) = lines.next_if(|line| !line.starts_with("commit ")) {
+				return Some((hash, name));
+			}
+		}
+	})
+}
+
+// the newest commit reachable from `commit` that was committed by `until`, in any date format git understands.
+// git blame has no `--until` of its own
+pub fn last_commit_until(repo: &Repository, commit: Oid, until: &str) -> Result<Option<Oid>, Box<dyn error::Error>> {
+	let mut command = process::Command::new("git");
+	command
+		.args(["rev-list", "-1", &format!("--until={}", until), &commit.to_string()])
+		.current_dir(repo.path());
+	let output = command.output()?;
+	log::debug!("ran {:?}", command);
+	if !output.status.success() {
+		return Err(String::from_utf8_lossy(&output.stderr).into());
+	}
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	Ok(match stdout.trim() {
+		"" => None,
+		hash => Some(Oid::from_str(hash)?),
+	})
+}
+
+// where `origin` (or else the first remote) is hosted, for linking to code there
+pub fn remote(repo: &Repository) -> Option<crate::forge::Remote> {
+	let remote = repo.find_remote("origin").ok().or_else(|| {
+		let names = repo.remotes().ok()?;
+		let name = names.get(0)?;
+		repo.find_remote(name).ok()
+	})?;
+	crate::forge::Remote::parse(remote.url()?)
+}
+
+// every file in `commit`'s tree (or the index, for the null commit), as paths relative to the repository root
+pub fn tracked_files(repo: &Repository, commit: Oid) -> Result<Vec<String>, git2::Error> {
+	if commit.is_zero() {
+		// stage 0 leaves out the sides of a conflict
+		let index = repo.index()?;
+		let staged = index.iter().filter(|entry| (entry.flags >> 12) & 0x3 == 0);
+		return Ok(staged
+			.map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+			.collect());
+	}
+	let tree = repo.find_commit(commit)?.tree()?;
+	let mut files = vec![];
+	tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+		if entry.kind() == Some(git2::ObjectType::Blob) {
+			files.push(format!("{}{}", root, String::from_utf8_lossy(entry.name_bytes())));
+		}
+		git2::TreeWalkResult::Ok
+	})?;
+	Ok(files)
+}
+
+// the file's content at `commit`, read straight from the commit's tree or the index
+pub fn file_lines(repo: &Repository, rel_path: &Path, commit: Oid) -> Result<Vec<String>, git2::Error> {
+	let blob = blob_at(repo, rel_path, commit)?;
+	let content = String::from_utf8_lossy(blob.content());
+	Ok(content.lines().map(str::to_owned).collect())
+}
+
+// the file as of `commit` with line numbers, `highlight` (1-based) drawn in `highlight_style`
+pub fn file_at_commit(
+	repo: &Repository,
+	rel_path: &Path,
+	commit: Oid,
+	highlight: usize,
+	highlight_style: Style,
+	abbrev: usize,
+) -> Text<'static> {
+	let file_lines = match file_lines(repo, rel_path, commit) {
+		Ok(file_lines) => file_lines,
 		Err(e) => return Text::raw(e.to_string()),
 	};
-	let author = commit.author();
+	let mut lines = vec![Line::from(Span::styled(
+		format!("{} at {:.*}", rel_path.display(), abbrev, commit.to_string()),
+		Style::default().fg(Color::Yellow),
+	))];
+	for (i, code) in file_lines.iter().enumerate() {
+		let mut line = Line::from(vec![
+			line_number_span(i32::try_from(i + 1).unwrap_or(i32::MAX)),
+			Span::raw(layout::expand_tabs(code)),
+		]);
+		if i + 1 == highlight {
+			line.patch_style(highlight_style);
+		}
+		lines.push(line);
+	}
+	Text::from(lines)
+}
+
+// a rendered commit, and which file line each of its diff lines is
+#[derive(Clone)]
+pub struct CommitView {
+	pub text: Text<'static>,
+	pub locations: Vec<Option<DiffLocation>>, // one per line of `text`; None for everything but diff content
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffLocation {
+	pub path: PathBuf,
+	pub new_line: Option<u32>, // None for a deleted line
+	pub old_line: Option<u32>, // None for an added line
+}
+
+impl From<Text<'static>> for CommitView {
+	fn from(text: Text<'static>) -> CommitView {
+		CommitView {
+			text,
+			locations: vec![],
+		}
+	}
+}
+
+// `parent` is 1-based like git's `^N`; 0 asks for a combined diff against every parent of a merge
+// `ignore_whitespace` hides whitespace-only changes, like `git show -w`. the message body is wrapped to `width`, and
+// parents' hashes are cut to `abbrev`
+pub fn show(
+	repo: &Repository,
+	commit_id: Oid,
+	parent: usize,
+	color: bool,
+	ignore_whitespace: bool,
+	width: usize,
+	abbrev: usize,
+) -> CommitView {
+	let commit = match repo.find_commit(commit_id) {
+		Ok(commit) => commit,
+		Err(e) => return Text::raw(e.to_string()).into(),
+	};
+	let author = mailmapped_author(repo, &commit);
 	let commit_time = commit.time();
 	let time = chrono::DateTime::from_timestamp(commit_time.seconds(), 0).unwrap();
 	let mut lines = vec![
@@ -115,42 +879,162 @@ pub fn show(repo: &Repository, commit_id: Oid) -> Text<'static> {
 			author.email().unwrap_or_default()
 		)),
 		Line::from(format!("date: {}", time.with_timezone(&chrono::Local))),
+	];
+	let is_merge = commit.parent_count() > 1;
+	if is_merge {
+		let parents: Vec<String> = commit
+			.parent_ids()
+			.map(|id| format!("{:.*}", abbrev, id.to_string()))
+			.collect();
+		lines.push(Line::from(Span::styled(
+			format!("merge: {}", parents.join(" ")),
+			Style::default().fg(Color::Magenta),
+		)));
+	}
+	lines.append(&mut vec![
 		Line::default(),
 		Line::from(commit.summary().unwrap_or_default().to_owned()),
 		Line::default(),
-	];
+	]);
 	if let Some(body) = commit.body() {
-		push_lines(&mut lines, body, Color::Reset);
+		// blank lines between paragraphs are kept as they are
+		for line in body.split('\n') {
+			lines.extend(layout::wrap(line, width).into_iter().map(Line::from));
+		}
 		lines.push(Line::default());
 	}
-	let diff_cb = |_: git2::DiffDelta, _: Option<git2::DiffHunk>, diff_line: git2::DiffLine| -> bool {
-		let content = std::str::from_utf8(diff_line.content()).expect("couldn't decode diff line");
+	if is_merge && parent > 0 {
+		let parent_id = commit
+			.parent_id(parent - 1)
+			.map(|id| id.to_string())
+			.unwrap_or_default();
+		lines.push(Line::from(Span::styled(
+			format!(
+				"diff against parent {} of {}: {:.*} (0 for combined, 1-{} for a parent)",
+				parent,
+				commit.parent_count(),
+				abbrev,
+				parent_id,
+				commit.parent_count().min(9)
+			),
+			Style::default().fg(Color::Magenta),
+		)));
+	}
+	if is_merge && parent == 0 {
+		// libgit2 can only diff two trees, so let git produce the combined diff against every parent
+		lines.push(Line::from(Span::styled(
+			"combined diff against all parents (--cc)",
+			Style::default().fg(Color::Magenta),
+		)));
+		let commit_arg = commit_id.to_string();
+		let mut args = vec!["show", color_arg(color), "--cc", "--format="];
+		if ignore_whitespace {
+			args.push("-w");
+		}
+		args.push(&commit_arg);
+		let args: Vec<&OsStr> = args.into_iter().map(OsStr::new).collect();
+		lines.append(&mut git_output_to_text(repo.path(), &args, color).lines);
+		return Text::from(lines).into();
+	}
+	let diff = match diff_for_commit(repo, &commit, parent.max(1) - 1, ignore_whitespace) {
+		Ok(diff) => diff,
+		Err(e) => return Text::raw(e.to_string()).into(),
+	};
+	let mut locations = vec![];
+	let diff_cb = |delta: git2::DiffDelta, _: Option<git2::DiffHunk>, diff_line: git2::DiffLine| -> bool {
+		let content = String::from_utf8_lossy(diff_line.content());
 		let sigil = match diff_line.origin_value() {
 			DiffLineType::Addition => "+",
 			DiffLineType::Deletion => "-",
 			DiffLineType::Context => " ",
 			_ => "",
 		};
-		let line = format!("{}{}", sigil, content.replace('\t', "    ").trim_end_matches('\n'));
+		let line = format!("{}{}", sigil, layout::expand_tabs(&content).trim_end_matches('\n'));
 		let color = match diff_line.origin_value() {
 			DiffLineType::FileHeader => Color::Cyan,
 			DiffLineType::HunkHeader => Color::Blue,
 			DiffLineType::Addition | DiffLineType::AddEOFNL => Color::Green,
 			DiffLineType::Deletion | DiffLineType::DeleteEOFNL => Color::Red,
+			// "Binary files a/... and b/... differ" in place of a patch
+			DiffLineType::Binary => Color::Magenta,
 			_ => Color::Reset,
 		};
+		let location = match diff_line.origin_value() {
+			DiffLineType::Addition | DiffLineType::Deletion | DiffLineType::Context => {
+				// a deleted line only exists in the old file, which may have had another name
+				let file = if diff_line.new_lineno().is_some() {
+					delta.new_file()
+				} else {
+					delta.old_file()
+				};
+				file.path().map(|path| DiffLocation {
+					path: path.to_owned(),
+					new_line: diff_line.new_lineno(),
+					old_line: diff_line.old_lineno(),
+				})
+			}
+			_ => None,
+		};
+		locations.resize(lines.len(), None);
 		push_lines(&mut lines, &line, color);
+		locations.resize(lines.len(), location);
 		true
 	};
 	if let Err(e) = diff.print(git2::DiffFormat::Patch, diff_cb) {
-		return Text::raw(e.to_string());
+		return Text::raw(e.to_string()).into();
+	}
+	CommitView {
+		text: Text::from(lines),
+		locations,
+	}
+}
+
+// blame goes through git, which maps authors with .mailmap, so anything else showing an author should agree with it
+fn mailmapped_author(repo: &Repository, commit: &git2::Commit) -> git2::Signature<'static> {
+	match repo.mailmap() {
+		Ok(mailmap) => commit
+			.author_with_mailmap(&mailmap)
+			.unwrap_or_else(|_| commit.author().to_owned()),
+		Err(_) => commit.author().to_owned(),
 	}
-	Text::from(lines)
 }
 
-fn diff_for_commit<'a>(repo: &'a Repository, commit: &git2::Commit<'a>) -> Result<git2::Diff<'a>, git2::Error> {
-	let parent = commit.parent(0)?;
-	return repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None);
+// the commit as a patch email like `git format-patch` writes, which `git am` and `git apply` accept. merges are
+// diffed against `parent` (1-based); there's no patch for a combined diff (0), which git can't apply
+pub fn patch(
+	repo: &Repository,
+	commit_id: Oid,
+	parent: usize,
+	ignore_whitespace: bool,
+) -> Result<Vec<u8>, git2::Error> {
+	let Some(parent_index) = parent.checked_sub(1) else {
+		return Err(git2::Error::from_str("a combined diff can't be written as a patch"));
+	};
+	let commit = repo.find_commit(commit_id)?;
+	let diff = diff_for_commit(repo, &commit, parent_index, ignore_whitespace)?;
+	let email = git2::Email::from_diff(
+		&diff,
+		1,
+		1,
+		&commit_id,
+		commit.summary().unwrap_or_default(),
+		commit.body().unwrap_or_default(),
+		&mailmapped_author(repo, &commit),
+		&mut git2::EmailCreateOptions::new(),
+	)?;
+	Ok(email.as_slice().to_vec())
+}
+
+fn diff_for_commit<'a>(
+	repo: &'a Repository,
+	commit: &git2::Commit<'a>,
+	parent_index: usize,
+	ignore_whitespace: bool,
+) -> Result<git2::Diff<'a>, git2::Error> {
+	let parent = commit.parent(parent_index)?;
+	let mut options = git2::DiffOptions::new();
+	options.ignore_whitespace(ignore_whitespace);
+	return repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), Some(&mut options));
 }
 
 fn push_lines(lines: &mut Vec<Line>, s: &str, color: Color) {
@@ -159,22 +1043,153 @@ fn push_lines(lines: &mut Vec<Line>, s: &str, color: Color) {
 	}
 }
 
-pub fn log_follow(repo: &Repository, rel_path: &Path, line_num: usize, start_commit: Oid) -> Text<'static> {
-	let repo_path = repo.workdir().unwrap();
-	let output = process::Command::new("git")
-		.args([
-			"log",
-			"--color=always",
-			"--abbrev-commit",
-			"--decorate",
-			"--date=local",
-			"--find-copies",
-			"-L",
-			&format!("{},{}:{}", line_num + 1, line_num + 1, rel_path.display()),
-			&start_commit.to_string(),
-		])
-		.current_dir(repo_path)
-		.output();
+// whether NO_COLOR (https://no-color.org) asks for no colors
+pub fn no_color() -> bool {
+	env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+// whether git's own output should be colored, following NO_COLOR and then color.diff/color.ui like `git log` does
+pub fn color_enabled(repo: &Repository) -> bool {
+	if no_color() {
+		return false;
+	}
+	let config = match repo.config() {
+		Ok(config) => config,
+		Err(_) => return true,
+	};
+	let setting = config
+		.get_string("color.diff")
+		.or_else(|_| config.get_string("color.ui"))
+		.unwrap_or_default();
+	// "auto" means color on a terminal, which is always where this output ends up
+	!matches!(setting.to_lowercase().as_str(), "false" | "never" | "no" | "off" | "0")
+}
+
+pub fn log_follow(
+	repo: &Repository,
+	rel_path: &Path,
+	line_num: usize,
+	start_commit: Oid,
+	color: bool,
+) -> Text<'static> {
+	let repo_path = repo.workdir().unwrap_or(repo.path());
+	// built as an OsString so paths that aren't UTF-8 survive
+	let mut line_range = OsString::from(format!("{},{}:", line_num + 1, line_num + 1));
+	line_range.push(rel_path);
+	let start_commit = start_commit.to_string();
+	let mut args = [
+		"log",
+		color_arg(color),
+		"--abbrev-commit",
+		"--decorate",
+		"--date=local",
+		"--find-copies",
+		"-L",
+	]
+	.map(OsStr::new)
+	.to_vec();
+	args.extend([&line_range, OsStr::new(&start_commit), OsStr::new("--")]);
+	mark_file_boundaries(git_output_to_text(repo_path, &args, color))
+}
+
+// the line's history can cross renames and copies, so a header goes wherever the file it's in changes. git's own
+// diff headers are left as git colored them
+fn mark_file_boundaries(text: Text<'static>) -> Text<'static> {
+	let mut lines = Vec::with_capacity(text.lines.len());
+	let mut current_path: Option<String> = None;
+	for line in text.lines {
+		let content: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+		if let Some(paths) = content.strip_prefix("diff --git ") {
+			let path = paths.rsplit_once(" b/").map_or(paths, |(_, path)| path).to_owned();
+			if current_path.as_ref() != Some(&path) {
+				lines.push(Line::from(Span::styled(
+					format!("── {} ──", path),
+					Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+				)));
+				current_path = Some(path);
+			}
+		}
+		lines.push(line);
+	}
+	Text::from(lines)
+}
+
+fn color_arg(color: bool) -> &'static str {
+	if color {
+		"--color=always"
+	} else {
+		"--color=never"
+	}
+}
+
+// the reverse of ansi-to-tui: `text` as a terminal would need it printed, with SGR escapes for its styles when
+// `color` is set
+pub fn text_to_ansi(text: &Text, color: bool) -> String {
+	let mut out = String::new();
+	for line in &text.lines {
+		for span in &line.spans {
+			let codes = if color { sgr_codes(span.style) } else { vec![] };
+			if codes.is_empty() {
+				out.push_str(&span.content);
+			} else {
+				out.push_str(&format!("\x1b[{}m{}\x1b[m", codes.join(";"), span.content));
+			}
+		}
+		out.push('\n');
+	}
+	out
+}
+
+fn sgr_codes(style: Style) -> Vec<String> {
+	let mut codes = vec![];
+	for (modifier, code) in [
+		(Modifier::BOLD, "1"),
+		(Modifier::DIM, "2"),
+		(Modifier::ITALIC, "3"),
+		(Modifier::UNDERLINED, "4"),
+		(Modifier::REVERSED, "7"),
+		(Modifier::CROSSED_OUT, "9"),
+	] {
+		if style.add_modifier.contains(modifier) {
+			codes.push(code.to_owned());
+		}
+	}
+	codes.extend(style.fg.and_then(|fg| color_code(fg, 30)));
+	codes.extend(style.bg.and_then(|bg| color_code(bg, 40)));
+	codes
+}
+
+// `base` is 30 for the foreground and 40 for the background
+fn color_code(color: Color, base: u8) -> Option<String> {
+	let offset = match color {
+		Color::Reset => return None,
+		Color::Black => 0,
+		Color::Red => 1,
+		Color::Green => 2,
+		Color::Yellow => 3,
+		Color::Blue => 4,
+		Color::Magenta => 5,
+		Color::Cyan => 6,
+		Color::Gray => 7,
+		Color::DarkGray => 60,
+		Color::LightRed => 61,
+		Color::LightGreen => 62,
+		Color::LightYellow => 63,
+		Color::LightBlue => 64,
+		Color::LightMagenta => 65,
+		Color::LightCyan => 66,
+		Color::White => 67,
+		Color::Indexed(i) => return Some(format!("{};5;{}", base + 8, i)),
+		Color::Rgb(r, g, b) => return Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+	};
+	Some((base + offset).to_string())
+}
+
+// run git and render whatever it printed, falling back to stderr when it fails
+fn git_output_to_text(dir: &Path, args: &[&OsStr], color: bool) -> Text<'static> {
+	let started = Instant::now();
+	let output = process::Command::new("git").args(args).current_dir(dir).output();
+	log::debug!("ran git {:?} in {:?}", args, started.elapsed());
 
 	let buf = match output {
 		Ok(o) => {
@@ -188,8 +1203,630 @@ pub fn log_follow(repo: &Repository, rel_path: &Path, line_num: usize, start_com
 			return Text::raw(e.to_string());
 		}
 	};
-	match buf.into_text() {
+	let output = drop_overwritten(&String::from_utf8_lossy(&buf));
+	if !color {
+		return Text::raw(layout::expand_tabs(&output));
+	}
+	match output.into_text() {
 		Ok(t) => t,
-		Err(e) => Text::raw(format!("ansi_to_tui:\n{}", e)),
+		Err(e) => {
+			// the history is still worth seeing without its colors
+			log::error!("couldn't convert git's colors: {}", e);
+			Text::raw(layout::expand_tabs(&strip_ansi(&output)))
+		}
+	}
+}
+
+// a carriage return sends the cursor back to overwrite the line, as progress meters do, so keep only what a
+// terminal would have ended up showing
+fn drop_overwritten(s: &str) -> String {
+	let lines: Vec<&str> = s
+		.split('\n')
+		.map(|line| line.trim_end_matches('\r').rsplit('\r').next().unwrap_or_default())
+		.collect();
+	lines.join("\n")
+}
+
+// removes terminal escape sequences: CSI ones like colors (`ESC [ ... m`), OSC ones like hyperlinks
+// (`ESC ] ... BEL`) and two-character ones
+fn strip_ansi(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut chars = s.chars().peekable();
+	while let Some(c) = chars.next() {
+		if c != '\x1b' {
+			out.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('[') => {
+				for c in chars.by_ref() {
+					if ('\x40'..='\x7e').contains(&c) {
+						break;
+					}
+				}
+			}
+			Some(']') => {
+				while let Some(c) = chars.next() {
+					if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+						break;
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use git2::Oid;
+	use std::{
+		cell::OnceCell,
+		ffi::OsStr,
+		fs,
+		path::{Path, PathBuf},
+		process,
+		rc::Rc,
+		time::{Duration, SystemTime},
+	};
+
+	use tui::{
+		style::{Color, Modifier, Style},
+		text::{Line, Span, Text},
+	};
+
+	use crate::temp_repo::TempRepo;
+
+	use super::{
+		author_color, blame, blame_command, blame_parent, check_output, drop_overwritten, file_at_commit, follow_path,
+		format_blame_line, format_line_num_and_code, format_time, has_staged_changes, initials, last_commit_until,
+		log_names, mark_file_boundaries, patch, show, strip_ansi, text_to_ansi, BlameHunk, BlameOptions, Column,
+		Columns, CommitMeta, TimeZone, DEFAULT_COLUMNS,
+	};
+
+	fn default_columns() -> Columns {
+		Columns {
+			hash_width: 8,
+			author_width: 12,
+			time_width: 13,
+			summary_width: 30,
+			show_hash: true,
+			show_author: true,
+			show_time: true,
+			show_summary: false,
+			show_line_num: true,
+			order: DEFAULT_COLUMNS.to_vec(),
+			highlight_trailing_whitespace: false,
+			mark_blank_lines: false,
+			no_color: false,
+			mark_rewritten: false,
+			mark_merges: false,
+			author_initials: false,
+			right_align_author: false,
+			human_dates: false,
+			human_threshold: Duration::from_secs(7 * 24 * 60 * 60),
+			time_zone: TimeZone::Local,
+			recent_threshold: None,
+		}
+	}
+
+	#[test]
+	fn author_initials() {
+		assert_eq!(initials("Ada King Lovelace"), "AKL");
+		assert_eq!(initials("grace  hopper"), "GH");
+		assert_eq!(initials("Ægir Jón Ólafsson Smith"), "ÆJÓ");
+		assert_eq!(initials(""), "");
+		assert_eq!(author_color("raylu"), author_color("raylu"));
+	}
+
+	#[test]
+	fn human_dates() {
+		let mut columns = default_columns();
+		let day = Duration::from_secs(24 * 60 * 60);
+		let now = SystemTime::UNIX_EPOCH + 20_000 * day; // 2024-10-04
+		assert_eq!(format_time(now - 400 * day, 0, now, &columns), "1 year ago");
+		columns.human_dates = true;
+		assert_eq!(format_time(now - 2 * day, 0, now, &columns), "2 days ago");
+		// in UTC, so the date doesn't depend on the timezone the tests run in
+		columns.time_zone = TimeZone::Utc;
+		assert_eq!(format_time(now - 30 * day, 0, now, &columns), "Sep 4");
+		assert_eq!(format_time(now - 400 * day, 0, now, &columns), "Aug 31 2023");
+		// late on Sep 4 in UTC is already Sep 5 in the committer's UTC+14
+		let late = Duration::from_secs(23 * 60 * 60);
+		assert_eq!(format_time(now - 30 * day + late, 14 * 60 * 60, now, &columns), "Sep 4");
+		columns.time_zone = TimeZone::Committer;
+		assert_eq!(format_time(now - 30 * day + late, 14 * 60 * 60, now, &columns), "Sep 5");
+	}
+
+	#[test]
+	fn rewritten_marker() {
+		let day = Duration::from_secs(24 * 60 * 60);
+		let now = SystemTime::UNIX_EPOCH + 20_000 * day;
+		let hunk = |author_time| BlameHunk {
+			commit: Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap(),
+			path: None,
+			previous: None,
+			orig_line_num: 1,
+			line_num: 1,
+			code: "code".to_owned(),
+			hunk_start: true,
+			meta: Rc::new(CommitMeta {
+				author: "raylu".to_owned(),
+				author_mail: "raylu@example.com".to_owned(),
+				author_time,
+				author_tz: 0,
+				committer: "raylu".to_owned(),
+				committer_mail: "raylu@example.com".to_owned(),
+				commit_time: now - 2 * day,
+				commit_tz: 0,
+				summary: "summary".to_owned(),
+				is_merge: OnceCell::new(),
+				boundary: false,
+			}),
+		};
+		let time_column = |hunk: &BlameHunk, columns: &Columns| {
+			let line = format_blame_line(hunk, columns, now);
+			line.spans[4].content.clone().into_owned()
+		};
+		let mut columns = default_columns();
+		columns.mark_rewritten = true;
+		// rebased: authored a week before it was committed
+		let rebased = hunk(now - 9 * day);
+		assert!(rebased.meta.is_rewritten());
+		assert_eq!(time_column(&rebased, &columns), "2 days ago  *");
+		let committed = hunk(now - 2 * day);
+		assert!(!committed.meta.is_rewritten());
+		assert_eq!(time_column(&committed, &columns), "2 days ago   ");
+		columns.mark_rewritten = false;
+		assert_eq!(time_column(&rebased, &columns), "2 days ago   ");
+	}
+
+	#[test]
+	fn blank_lines() {
+		let mut columns = default_columns();
+		let code = |columns: &Columns, line| format_line_num_and_code(1, line, columns)[1..].to_vec();
+		assert_eq!(code(&columns, ""), [Span::raw("")]);
+		columns.mark_blank_lines = true;
+		assert_eq!(
+			code(&columns, ""),
+			[Span::styled("~", Style::default().fg(Color::DarkGray))]
+		);
+		assert_eq!(code(&columns, " "), [Span::raw(" ")]);
+		columns.no_color = true;
+		assert_eq!(code(&columns, ""), [Span::raw("~")]);
+	}
+
+	#[test]
+	fn column_order() {
+		assert_eq!(
+			Column::parse_list("line, hash,author"),
+			Ok(vec![Column::LineNumber, Column::Hash, Column::Author, Column::Code])
+		);
+		assert!(Column::parse_list("code,hash").is_err());
+		assert!(Column::parse_list("hash,hash,code").is_err());
+		assert!(Column::parse_list("hash,date").is_err());
+		assert_eq!(
+			Column::order(&[Column::Time, Column::Code]),
+			[
+				Column::Time,
+				Column::Hash,
+				Column::Author,
+				Column::Summary,
+				Column::LineNumber,
+				Column::Code
+			]
+		);
+
+		let temp = TempRepo::new("columns");
+		fs::write(temp.dir.join("a.txt"), "a\nb\n").unwrap();
+		let commit = temp.commit_all("add a.txt");
+		let options = BlameOptions {
+			root: true,
+			..BlameOptions::default()
+		};
+		let blame = blame(&temp.repo, Path::new("a.txt"), commit, &options).unwrap();
+
+		let columns = Columns {
+			show_author: false,
+			show_time: false,
+			order: Column::order(&[Column::LineNumber, Column::Hash, Column::Code]),
+			..default_columns()
+		};
+		let text = |line: Line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>();
+		let now = SystemTime::now();
+		let hash = &commit.to_string()[..8];
+		assert_eq!(
+			text(format_blame_line(&blame[0], &columns, now)),
+			format!("    1 {} a", hash)
+		);
+		assert_eq!(text(format_blame_line(&blame[1], &columns, now)), "    2          b");
+		assert_eq!(columns.hash_offset(1), Some(6));
+		assert_eq!(default_columns().hash_offset(1), Some(0));
+	}
+
+	#[test]
+	fn blame_command_options() {
+		let options = BlameOptions {
+			encoding: Some("latin1".to_owned()),
+			quote_path: false,
+			since: Some("2.weeks.ago".to_owned()),
+			ignore_whitespace: true,
+			detect_moves: false,
+			detect_copies: true,
+			first_parent: true,
+			root: true,
+			ignore_revs_file: Some(".git-blame-ignore-revs".to_owned()),
+			ignore_revs: vec![Oid::from_str("89abcdef0123456789abcdef0123456789abcdef").unwrap()],
+		};
+		let commit = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+		let command = blame_command(Path::new("/repo"), Path::new("src/git.rs"), commit, &options);
+		let args: Vec<&OsStr> = command.get_args().collect();
+		assert_eq!(
+			args,
+			[
+				"-c",
+				"core.quotepath=false",
+				"blame",
+				"--porcelain",
+				"--encoding=latin1",
+				"--since=2.weeks.ago",
+				"-w",
+				"-C",
+				"--first-parent",
+				"--root",
+				"--ignore-revs-file=.git-blame-ignore-revs",
+				"--ignore-rev=89abcdef0123456789abcdef0123456789abcdef",
+				"0123456789abcdef0123456789abcdef01234567",
+				"--",
+				"src/git.rs",
+			]
+		);
+	}
+
+	#[test]
+	fn blame_encoding() {
+		let temp = TempRepo::new("encoding");
+		fs::write(temp.dir.join("a.txt"), "line\n").unwrap();
+		let mut index = temp.repo.index().unwrap();
+		index.add_path(Path::new("a.txt")).unwrap();
+		// written by hand, since libgit2 only makes UTF-8 commits. old repositories with i18n.commitEncoding set
+		// have commits like this
+		let mut raw = format!("tree {}\n", index.write_tree().unwrap()).into_bytes();
+		raw.extend(b"author Ren\xe9 Fran\xe7ois <rene@example.com> 1234567890 +0000\n");
+		raw.extend(b"committer Ren\xe9 Fran\xe7ois <rene@example.com> 1234567890 +0000\n");
+		raw.extend(b"encoding ISO-8859-1\n\ncaf\xe9\n");
+		let commit = temp.repo.odb().unwrap().write(git2::ObjectType::Commit, &raw).unwrap();
+
+		let options = |encoding: &str| BlameOptions {
+			encoding: Some(encoding.to_owned()),
+			root: true,
+			..BlameOptions::default()
+		};
+		let reencoded = blame(&temp.repo, Path::new("a.txt"), commit, &options("UTF-8")).unwrap();
+		assert_eq!(reencoded[0].meta.author, "René François");
+		assert_eq!(reencoded[0].meta.summary, "café");
+		// `none` passes the latin1 bytes through, which aren't UTF-8
+		let raw = blame(&temp.repo, Path::new("a.txt"), commit, &options("none")).unwrap();
+		assert_eq!(raw[0].meta.author, "Ren\u{FFFD} Fran\u{FFFD}ois");
+	}
+
+	#[test]
+	fn blame_leading_dash() {
+		let temp = TempRepo::new("dash");
+		fs::write(temp.dir.join("-n"), "looks like an option\n").unwrap();
+		let commit = temp.commit_all("add -n");
+
+		let blame = blame(&temp.repo, Path::new("-n"), commit, &BlameOptions::default())
+			.expect("couldn't blame a file named -n");
+		assert_eq!(blame.len(), 1);
+		assert_eq!(blame[0].code, "looks like an option");
+		assert_eq!(blame[0].commit, commit);
+	}
+
+	#[test]
+	fn stderr_on_success() {
+		let run = |script| process::Command::new("sh").args(["-c", script]).output().unwrap();
+		let output = run("echo '<sha> 1 1 1'; echo 'warning: careful' >&2");
+		assert_eq!(check_output(&output), Ok(Some("warning: careful".to_owned())));
+		// warnings don't end up in what's parsed
+		assert_eq!(output.stdout, b"<sha> 1 1 1\n");
+		assert_eq!(check_output(&run("echo '<sha> 1 1 1'")), Ok(None));
+		assert_eq!(
+			check_output(&run("echo 'fatal: no such path' >&2; exit 128")),
+			Err("fatal: no such path".to_owned())
+		);
+	}
+
+	#[test]
+	fn file_boundaries() {
+		let bold = Style::default().add_modifier(Modifier::BOLD);
+		let header = |paths: &str| Line::from(Span::styled(format!("diff --git {}", paths), bold));
+		let text = Text::from(vec![
+			header("a/old.rs b/old.rs"),
+			Line::from("+a"),
+			header("a/old.rs b/old.rs"),
+			Line::from("-b"),
+			header("a/old.rs b/new.rs"),
+			Line::from("+c"),
+		]);
+		let banner = |path: &str| {
+			Line::from(Span::styled(
+				format!("── {} ──", path),
+				Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+			))
+		};
+		assert_eq!(
+			mark_file_boundaries(text).lines,
+			[
+				banner("old.rs"),
+				header("a/old.rs b/old.rs"),
+				Line::from("+a"),
+				header("a/old.rs b/old.rs"),
+				Line::from("-b"),
+				banner("new.rs"),
+				header("a/old.rs b/new.rs"),
+				Line::from("+c"),
+			]
+		);
+	}
+
+	#[test]
+	fn commit_until() {
+		let temp = TempRepo::new("until");
+		let at = |seconds| git2::Signature::new("raylu", "raylu@example.com", &git2::Time::new(seconds, 0)).unwrap();
+		let commit = |seconds, parents: &[&git2::Commit]| {
+			let tree = temp
+				.repo
+				.find_tree(temp.repo.index().unwrap().write_tree().unwrap())
+				.unwrap();
+			let id = temp
+				.repo
+				.commit(None, &at(seconds), &at(seconds), "commit", &tree, parents)
+				.unwrap();
+			temp.repo.find_commit(id).unwrap()
+		};
+		let first = commit(1_704_067_200, &[]); // 2024-01-01
+		let second = commit(1_706_745_600, &[&first]); // 2024-02-01
+
+		let until = |date| last_commit_until(&temp.repo, second.id(), date).unwrap();
+		assert_eq!(until("2024-03-01"), Some(second.id()));
+		assert_eq!(until("2024-01-15"), Some(first.id()));
+		assert_eq!(until("2023-12-01"), None);
+	}
+
+	#[test]
+	fn file_at_commit_gutter() {
+		let temp = TempRepo::new("file-at-commit");
+		fs::write(temp.dir.join("a.txt"), "one\ntwo\n").unwrap();
+		let commit = temp.commit_all("add a.txt");
+		let highlighted = Style::default().add_modifier(Modifier::REVERSED);
+		let text = file_at_commit(&temp.repo, Path::new("a.txt"), commit, 2, highlighted, 8);
+		// the same gutter as the blame's
+		assert_eq!(
+			text.lines[1].spans,
+			format_line_num_and_code(1, "one", &default_columns())
+		);
+		assert_eq!(
+			text.lines[2],
+			Line::from(vec![
+				Span::styled(
+					"    2 ",
+					Style::default().fg(Color::DarkGray).add_modifier(Modifier::REVERSED)
+				),
+				Span::styled("two", highlighted),
+			])
+		);
+	}
+
+	#[test]
+	fn blame_binary() {
+		let temp = TempRepo::new("binary");
+		fs::write(temp.dir.join("image.png"), b"\x89PNG\0\0\0").unwrap();
+		let commit = temp.commit_all("add image");
+
+		let result = blame(&temp.repo, Path::new("image.png"), commit, &BlameOptions::default());
+		let err = result.expect_err("blamed a binary file");
+		assert!(err.to_string().contains("binary file"));
+	}
+
+	#[test]
+	fn blame_mailmap() {
+		let temp = TempRepo::new("mailmap");
+		fs::write(temp.dir.join(".mailmap"), "Ray Lu <ray@lu.dev> <raylu@example.com>\n").unwrap();
+		temp.commit_all("add .mailmap");
+		fs::write(temp.dir.join("a.txt"), "line\n").unwrap();
+		let commit = temp.commit_all("add a.txt");
+
+		let blame = blame(&temp.repo, Path::new("a.txt"), commit, &BlameOptions::default()).unwrap();
+		let shown = show(&temp.repo, commit, 1, false, false, 80, 8);
+		assert_eq!(blame[0].meta.author, "Ray Lu");
+		assert_eq!(blame[0].meta.author_mail, "ray@lu.dev");
+		assert_eq!(shown.text.lines[1], Line::from("author: Ray Lu <ray@lu.dev>"));
+	}
+
+	#[test]
+	fn merge_patch() {
+		let temp = TempRepo::new("patch");
+		fs::write(temp.dir.join("a.txt"), "one\n").unwrap();
+		let first = temp.repo.find_commit(temp.commit_all("add a.txt")).unwrap();
+		fs::write(temp.dir.join("a.txt"), "two\n").unwrap();
+		let second = temp.repo.find_commit(temp.commit_all("change a.txt")).unwrap();
+		// merges `first` into `second`, keeping `second`'s a.txt
+		let signature = git2::Signature::now("raylu", "raylu@example.com").unwrap();
+		let merge = temp
+			.repo
+			.commit(
+				None,
+				&signature,
+				&signature,
+				"merge",
+				&second.tree().unwrap(),
+				&[&second, &first],
+			)
+			.unwrap();
+
+		let patch = |parent| patch(&temp.repo, merge, parent, false).map(|patch| String::from_utf8(patch).unwrap());
+		assert!(!patch(1).unwrap().contains("a.txt"));
+		assert!(patch(2).unwrap().contains("-one\n+two\n"));
+		assert!(patch(0).is_err());
+
+		let shown = show(&temp.repo, merge, 2, false, false, 80, 12);
+		let merge_line: String = shown.text.lines[3]
+			.spans
+			.iter()
+			.map(|span| span.content.as_ref())
+			.collect();
+		assert_eq!(
+			merge_line,
+			format!("merge: {:.12} {:.12}", second.id().to_string(), first.id().to_string())
+		);
+	}
+
+	#[test]
+	fn follow_path_across_rename() {
+		let temp = TempRepo::new("follow");
+		fs::write(temp.dir.join("a.txt"), "1\n").unwrap();
+		let added = temp.commit_all("add a.txt");
+		fs::rename(temp.dir.join("a.txt"), temp.dir.join("b.txt")).unwrap();
+		let renamed = temp.commit_all("rename to b.txt");
+
+		let follow = |commit| follow_path(&temp.repo, Path::new("b.txt"), commit).unwrap();
+		assert_eq!(follow(renamed), Some(PathBuf::from("b.txt")));
+		assert_eq!(follow(added), Some(PathBuf::from("a.txt")));
+	}
+
+	#[test]
+	fn follow_path_from_side_branch() {
+		let temp = TempRepo::new("follow-side");
+		fs::write(temp.dir.join("a.txt"), "1\n").unwrap();
+		let added = temp.commit_all("add a.txt");
+		let side = temp.commit("a.txt", "2\n", "change a.txt on a side branch", &[added]);
+		// HEAD goes back to the main line and renames the file there
+		temp.repo.set_head_detached(added).unwrap();
+		fs::remove_file(temp.dir.join("a.txt")).unwrap();
+		fs::write(temp.dir.join("b.txt"), "1\n").unwrap();
+		temp.commit_all("rename to b.txt");
+
+		assert!(!temp
+			.repo
+			.graph_descendant_of(temp.repo.head().unwrap().target().unwrap(), side)
+			.unwrap());
+		assert_eq!(
+			follow_path(&temp.repo, Path::new("b.txt"), side).unwrap(),
+			Some(PathBuf::from("a.txt"))
+		);
+	}
+
+	#[test]
+	fn names_in_log() {
+		// the merge has no name of its own
+		let log = "commit 1111\n\nb.txt\ncommit 2222\ncommit 3333\n\nb.txt\ncommit 4444\n\na.txt\n";
+		assert_eq!(
+			log_names(log).collect::<Vec<_>>(),
+			[("1111", "b.txt"), ("3333", "b.txt"), ("4444", "a.txt")]
+		);
+	}
+
+	#[test]
+	fn blame_parent_across_rename() {
+		let temp = TempRepo::new("rename");
+		fs::write(temp.dir.join("old.txt"), "one\ntwo\nthree\n").unwrap();
+		let added = temp.commit_all("add old.txt");
+		fs::remove_file(temp.dir.join("old.txt")).unwrap();
+		fs::write(temp.dir.join("new.txt"), "one\n2\nthree\n").unwrap();
+		let renamed = temp.commit_all("rename old.txt to new.txt");
+
+		let options = BlameOptions::default();
+		let new_blame = blame(&temp.repo, Path::new("new.txt"), renamed, &options).unwrap();
+		let (commit, path) = blame_parent(&temp.repo, &new_blame[1], Path::new("new.txt")).unwrap();
+		let old_blame = blame(&temp.repo, &path, commit, &options).unwrap();
+		assert_eq!(new_blame[1].commit, renamed);
+		assert_eq!(new_blame[1].path.as_deref(), Some(Path::new("new.txt")));
+		assert_eq!((commit, path.as_path()), (added, Path::new("old.txt")));
+		assert_eq!(old_blame[1].code, "two");
+		assert_eq!(old_blame[1].commit, added);
+	}
+
+	#[test]
+	fn blame_staged() {
+		let temp = TempRepo::new("staged");
+		fs::write(temp.dir.join("a.txt"), "one\ntwo\n").unwrap();
+		let commit = temp.commit_all("add a.txt");
+		assert!(!has_staged_changes(&temp.repo, Path::new("a.txt")));
+		fs::write(temp.dir.join("a.txt"), "one\n2\n").unwrap();
+		let mut index = temp.repo.index().unwrap();
+		index.add_path(Path::new("a.txt")).unwrap();
+		index.write().unwrap();
+		// not staged, so not blamed
+		fs::write(temp.dir.join("a.txt"), "one\n2\nthree\n").unwrap();
+
+		assert!(has_staged_changes(&temp.repo, Path::new("a.txt")));
+		let blame = blame(&temp.repo, Path::new("a.txt"), Oid::zero(), &BlameOptions::default()).unwrap();
+		assert_eq!(blame.len(), 2);
+		assert_eq!(blame[0].commit, commit);
+		assert_eq!((blame[1].commit, blame[1].code.as_str()), (Oid::zero(), "2"));
+	}
+
+	#[test]
+	fn ansi() {
+		let text = Text::from(vec![
+			Line::from(vec![
+				Span::styled("abc", Style::default().fg(Color::Yellow)),
+				Span::raw(" "),
+				Span::styled(
+					"d",
+					Style::default().fg(Color::Indexed(208)).add_modifier(Modifier::BOLD),
+				),
+			]),
+			Line::from(Span::styled("+x", Style::default().fg(Color::Reset))),
+		]);
+		assert_eq!(text_to_ansi(&text, false), "abc d\n+x\n");
+		assert_eq!(
+			text_to_ansi(&text, true),
+			"\x1b[33mabc\x1b[m \x1b[1;38;5;208md\x1b[m\n+x\n"
+		);
+	}
+
+	#[test]
+	fn show_wraps_body() {
+		let temp = TempRepo::new("body");
+		temp.commit_all("initial commit");
+		fs::write(temp.dir.join("a.txt"), "line\n").unwrap();
+		let body = "a long paragraph that goes on for longer than the panel is wide\n\nsecond paragraph";
+		let commit = temp.commit_all(&format!("add a.txt\n\n{}", body));
+
+		let shown = show(&temp.repo, commit, 1, false, false, 30, 8);
+		let lines: Vec<String> = shown
+			.text
+			.lines
+			.iter()
+			.map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+			.collect();
+		let start = lines.iter().position(|line| line == "add a.txt").unwrap() + 2;
+		assert_eq!(
+			lines[start..start + 6],
+			[
+				"a long paragraph that goes on",
+				"for longer than the panel is",
+				"wide",
+				"",
+				"second paragraph",
+				"",
+			]
+		);
+	}
+
+	#[test]
+	fn raw_output_cleanup() {
+		assert_eq!(
+			strip_ansi("\x1b[33mcommit abc\x1b[m (\x1b[1;36mHEAD\x1b[m)"),
+			"commit abc (HEAD)"
+		);
+		assert_eq!(strip_ansi("\x1b]8;;https://x\x07link\x1b]8;;\x1b\\ text"), "link text");
+		assert_eq!(
+			drop_overwritten("Counting: 10%\rCounting: 100%\r\nfatal: oops\r\n"),
+			"Counting: 100%\nfatal: oops\n"
+		);
 	}
 }
@@ -9,7 +9,8 @@ use std::{
 use nom::{
 	bytes::complete::{tag, take_till, take_till1, take_until1, take_while1},
 	character::complete::line_ending,
-	combinator::{opt, peek},
+	combinator::{map_res, opt, peek},
+	error::{Error, ErrorKind},
 	sequence::{delimited, preceded, terminated, Tuple},
 	AsChar, IResult,
 };
@@ -17,6 +18,7 @@ use nom::{
 #[derive(Debug, PartialEq, Eq)]
 pub struct BlameLine<'a> {
 	pub commit: &'a str,
+	pub orig_line_num: i32, // line number in the blamed commit's version of the file
 	pub line_num: i32,
 	pub code: Vec<&'a str>,
 	pub info: Rc<CommitInfo<'a>>,
@@ -25,8 +27,17 @@ pub struct BlameLine<'a> {
 #[derive(Debug, PartialEq, Eq)]
 pub struct CommitInfo<'a> {
 	pub author: &'a str,
+	pub author_mail: &'a str, // without the angle brackets
+	pub author_time: SystemTime,
+	pub author_tz: i32, // seconds east of UTC
+	pub committer: &'a str,
+	pub committer_mail: &'a str,
 	pub commit_time: SystemTime,
+	pub commit_tz: i32,
+	pub summary: &'a str,
 	pub path: Option<&'a Path>,
+	pub previous: Option<(&'a str, &'a Path)>, // the commit this one's lines were blamed from, and the path there
+	pub boundary: bool, // blame stopped here: a root commit (without --root) or the oldest one after --since
 }
 
 pub fn parse_blame_porcelain(input: &str) -> Result<Vec<BlameLine<'_>>, Box<dyn error::Error>> {
@@ -43,11 +54,20 @@ pub fn parse_blame_porcelain(input: &str) -> Result<Vec<BlameLine<'_>>, Box<dyn
 			Err(e) => return Err(Box::new(e.map_input(|es| es.to_owned()))),
 		};
 
-		let commit_info = commits.entry(header.commit).or_insert_with(|| {
-			let commit_info;
-			(remaining, commit_info) = parse_commit_info(remaining).expect("expected commit info");
-			Rc::new(commit_info)
-		});
+		// only the first hunk from each commit has its info
+		let commit_info = match commits.get(header.commit) {
+			Some(commit_info) => commit_info.clone(),
+			None => {
+				let commit_info;
+				(remaining, commit_info) = match parse_commit_info(remaining) {
+					Ok(r) => r,
+					Err(e) => return Err(Box::new(e.map_input(|es| es.to_owned()))),
+				};
+				let commit_info = Rc::new(commit_info);
+				commits.insert(header.commit, commit_info.clone());
+				commit_info
+			}
+		};
 
 		let code_line;
 		(remaining, code_line) = parse_code(remaining)?;
@@ -65,9 +85,10 @@ pub fn parse_blame_porcelain(input: &str) -> Result<Vec<BlameLine<'_>>, Box<dyn
 
 		hunks.push(BlameLine {
 			commit: header.commit,
+			orig_line_num: header.orig_line_no,
 			line_num: header.line_no,
 			code,
-			info: commit_info.to_owned(),
+			info: commit_info,
 		});
 	}
 	Ok(hunks)
@@ -76,41 +97,51 @@ pub fn parse_blame_porcelain(input: &str) -> Result<Vec<BlameLine<'_>>, Box<dyn
 #[derive(Debug, PartialEq, Eq)]
 struct Header<'a> {
 	commit: &'a str,
+	orig_line_no: i32,
 	line_no: i32,
 	group_size: i32,
 }
 
-fn parse_header(input: &str) -> IResult<&str, Header> {
+fn parse_header(input: &str) -> IResult<&str, Header<'_>> {
 	let space = tag(" ");
+	// errors instead of overflowing on numbers too long for an i32
+	let number = || map_res(take_while1(is_digit), str::parse::<i32>);
 	let commit = terminated(take_until1(" "), &space);
-	let orig_line = terminated(take_until1(" "), &space);
-	let final_line = take_while1(is_digit);
-	let group_size = opt(preceded(&space, take_while1(is_digit)));
-	let (remaining, (commit, _, final_line, group_size, _)) =
-		(commit, orig_line, final_line, group_size, line_ending).parse(input)?;
+	let orig_line = terminated(number(), &space);
+	let group_size = opt(preceded(&space, number()));
+	let (remaining, (commit, orig_line_no, line_no, group_size, _)) =
+		(commit, orig_line, number(), group_size, line_ending).parse(input)?;
 	Ok((
 		remaining,
 		Header {
 			commit,
-			line_no: final_line.parse().unwrap(),
-			group_size: match group_size {
-				Some(b) => b.parse().unwrap(),
-				None => 1,
-			},
+			orig_line_no,
+			line_no,
+			group_size: group_size.unwrap_or(1),
 		},
 	))
 }
 
-fn parse_commit_info(input: &str) -> IResult<&str, CommitInfo> {
+fn parse_commit_info(input: &str) -> IResult<&str, CommitInfo<'_>> {
 	let mut parse_line = (
-		terminated(take_until1(" "), tag(" ")),
-		terminated(take_till1(is_line_ending), line_ending),
+		take_till1(|c: char| c == ' ' || is_line_ending(c)),
+		// `boundary` is the only field without a value
+		terminated(opt(preceded(tag(" "), take_till1(is_line_ending))), line_ending),
 	);
 
 	let mut ret = CommitInfo {
 		author: "",
+		author_mail: "",
+		author_time: SystemTime::UNIX_EPOCH,
+		author_tz: 0,
+		committer: "",
+		committer_mail: "",
 		commit_time: SystemTime::UNIX_EPOCH,
+		commit_tz: 0,
+		summary: "",
 		path: None,
+		previous: None,
+		boundary: false,
 	};
 	let mut remaining = input;
 	while !remaining.is_empty() {
@@ -121,19 +152,41 @@ fn parse_commit_info(input: &str) -> IResult<&str, CommitInfo> {
 			Err(_) => {
 				let (field, value);
 				(remaining, (field, value)) = parse_line.parse(remaining)?;
+				let value = value.unwrap_or_default();
 				match field {
 					"author" => ret.author = value,
+					"author-mail" => ret.author_mail = value.trim_start_matches('<').trim_end_matches('>'),
+					"author-time" => {
+						if let Ok(timestamp) = value.parse() {
+							ret.author_time = make_time(timestamp);
+						}
+					}
+					"author-tz" => ret.author_tz = parse_tz(value),
+					"committer" => ret.committer = value,
+					"committer-mail" => {
+						ret.committer_mail = value.trim_start_matches('<').trim_end_matches('>');
+					}
 					"committer-time" => {
-						let timestamp: u64 = value.parse().unwrap();
-						ret.commit_time = make_time(timestamp);
+						if let Ok(timestamp) = value.parse() {
+							ret.commit_time = make_time(timestamp);
+						}
 					}
+					"committer-tz" => ret.commit_tz = parse_tz(value),
+					"summary" => ret.summary = value,
 					"filename" => ret.path = Some(Path::new(value)),
+					"previous" => {
+						if let Some((commit, path)) = value.split_once(' ') {
+							ret.previous = Some((commit, Path::new(path)));
+						}
+					}
+					"boundary" => ret.boundary = true,
 					_ => {}
 				}
 			}
 		};
 	}
-	panic!("couldn't find code line starting with tab")
+	// the output was cut off before the code line
+	Err(nom::Err::Error(Error::new(remaining, ErrorKind::Eof)))
 }
 
 #[inline]
@@ -146,6 +199,16 @@ fn is_line_ending(c: char) -> bool {
 	c == '\n' || c == '\r'
 }
 
+// "+0530" is 5½ hours east of UTC
+fn parse_tz(tz: &str) -> i32 {
+	let (sign, digits) = match tz.strip_prefix('-') {
+		Some(digits) => (-1, digits),
+		None => (1, tz.trim_start_matches('+')),
+	};
+	let hhmm: i32 = digits.parse().unwrap_or(0);
+	sign * (hhmm / 100 * 60 * 60 + hhmm % 100 * 60)
+}
+
 fn make_time(ts: u64) -> SystemTime {
 	SystemTime::UNIX_EPOCH + time::Duration::from_secs(ts)
 }
@@ -163,6 +226,7 @@ mod tests {
 	use super::parse_commit_info;
 
 	const TEST_BLAME_OUTPUT: &str = include_str!("../fixtures/test_blame_output");
+	const TEST_BLAME_OUTPUT_LATIN1: &[u8] = include_bytes!("../fixtures/test_blame_output_latin1");
 
 	#[test]
 	fn parse() {
@@ -170,6 +234,7 @@ mod tests {
 		let expected = vec![
 			BlameLine {
 				commit: "116aa62bf54a39697e25f21d6cf6799f7faa1349",
+				orig_line_num: 2,
 				line_num: 1,
 				code: vec![
 					":mod:`gc` --- Garbage Collector interface",
@@ -180,22 +245,45 @@ mod tests {
 				],
 				info: Rc::new(CommitInfo {
 					author: "Georg Brandl",
+					author_mail: "georg@python.org",
+					author_time: make_time(1187188102),
+					author_tz: 0,
+					committer: "Georg Brandl",
+					committer_mail: "georg@python.org",
 					commit_time: make_time(1187188102),
+					commit_tz: 0,
+					summary: "Move the 3k reST doc tree in place.",
 					path: Some(Path::new("Doc/library/gc.rst")),
+					previous: None,
+					boundary: false,
 				}),
 			},
 			BlameLine {
 				commit: "fa089b9b0b926c04e5d57812b7d7653472787965",
+				orig_line_num: 6,
 				line_num: 6,
 				code: vec![""],
 				info: Rc::new(CommitInfo {
 					author: "Terry Jan Reedy",
+					author_mail: "tjreedy@udel.edu",
+					author_time: make_time(1465671774),
+					author_tz: -4 * 60 * 60,
+					committer: "Terry Jan Reedy",
+					committer_mail: "tjreedy@udel.edu",
 					commit_time: make_time(1465671774),
+					commit_tz: -4 * 60 * 60,
+					summary: "Issue #22558: Add remaining doc links to source code for Python-coded modules.",
 					path: Some(Path::new("Doc/library/gc.rst")),
+					previous: Some((
+						"c92bf83a829956e683a3d6bb1ae65aed74d7b92a",
+						Path::new("Doc/library/gc.rst"),
+					)),
+					boundary: false,
 				}),
 			},
 			BlameLine {
 				commit: "116aa62bf54a39697e25f21d6cf6799f7faa1349",
+				orig_line_num: 7,
 				line_num: 7,
 				code: vec![
 					".. moduleauthor:: Neil Schemenauer <nas@arctrix.com>",
@@ -204,18 +292,40 @@ mod tests {
 				],
 				info: Rc::new(CommitInfo {
 					author: "Georg Brandl",
+					author_mail: "georg@python.org",
+					author_time: make_time(1187188102),
+					author_tz: 0,
+					committer: "Georg Brandl",
+					committer_mail: "georg@python.org",
 					commit_time: make_time(1187188102),
+					commit_tz: 0,
+					summary: "Move the 3k reST doc tree in place.",
 					path: Some(Path::new("Doc/library/gc.rst")),
+					previous: None,
+					boundary: false,
 				}),
 			},
 			BlameLine {
 				commit: "fa089b9b0b926c04e5d57812b7d7653472787965",
+				orig_line_num: 10,
 				line_num: 10,
 				code: vec!["--------------"],
 				info: Rc::new(CommitInfo {
 					author: "Terry Jan Reedy",
+					author_mail: "tjreedy@udel.edu",
+					author_time: make_time(1465671774),
+					author_tz: -4 * 60 * 60,
+					committer: "Terry Jan Reedy",
+					committer_mail: "tjreedy@udel.edu",
 					commit_time: make_time(1465671774),
+					commit_tz: -4 * 60 * 60,
+					summary: "Issue #22558: Add remaining doc links to source code for Python-coded modules.",
 					path: Some(Path::new("Doc/library/gc.rst")),
+					previous: Some((
+						"c92bf83a829956e683a3d6bb1ae65aed74d7b92a",
+						Path::new("Doc/library/gc.rst"),
+					)),
+					boundary: false,
 				}),
 			},
 		];
@@ -232,6 +342,7 @@ mod tests {
 			result.1,
 			Header {
 				commit: "116aa62bf54a39697e25f21d6cf6799f7faa1349",
+				orig_line_no: 2,
 				line_no: 1,
 				group_size: 5,
 			}
@@ -259,9 +370,71 @@ filename Doc/library/gc.rst
 			commit_info,
 			CommitInfo {
 				author: "raylu",
+				author_mail: "mail@fake.tld",
+				author_time: SystemTime::UNIX_EPOCH + time::Duration::from_secs(1234567890),
+				author_tz: -11 * 60 * 60,
+				committer: "someguy",
+				committer_mail: "fake@mail.tld",
 				commit_time: SystemTime::UNIX_EPOCH + time::Duration::from_secs(9876543210),
+				commit_tz: 11 * 60 * 60,
+				summary: "blah blah",
 				path: Some(Path::new("Doc/library/gc.rst")),
+				previous: Some((
+					"c92bf83a829956e683a3d6bb1ae65aed74d7b92a",
+					Path::new("Doc/library/gc.rst")
+				)),
+				boundary: false,
 			}
 		);
+
+		let root = "author raylu
+author-mail <mail@fake.tld>
+author-time 1234567890
+author-tz -1100
+committer raylu
+committer-mail <mail@fake.tld>
+committer-time 1234567890
+committer-tz -1100
+summary initial commit
+boundary
+filename README
+	line of code
+";
+		let (remaining, commit_info) = parse_commit_info(root).expect("couldn't parse commit info");
+		assert_eq!(remaining, "\tline of code\n");
+		assert!(commit_info.boundary);
+		assert_eq!(commit_info.path, Some(Path::new("README")));
+		assert_eq!(commit_info.summary, "initial commit");
+	}
+
+	#[test]
+	fn truncated() {
+		assert_eq!(parse_blame_porcelain("").unwrap(), vec![]);
+		// cut off after every line, nothing panics, and only whole hunks parse
+		for (end, _) in TEST_BLAME_OUTPUT.match_indices('\n') {
+			let input = &TEST_BLAME_OUTPUT[..=end];
+			if let Ok(result) = parse_blame_porcelain(input) {
+				assert!(result.iter().all(|line| !line.code.is_empty()));
+			}
+		}
+		let header = "116aa62bf54a39697e25f21d6cf6799f7faa1349 2 1 5\n";
+		assert!(parse_blame_porcelain(header).is_err());
+		assert!(parse_blame_porcelain(&format!("{}author Georg Brandl\n", header)).is_err());
+		// a hunk that promises more lines than there are
+		assert!(parse_blame_porcelain(&format!("{}filename gc.rst\n\tcode\n", header)).is_err());
+		assert!(parse_blame_porcelain("116aa62bf54a39697e25f21d6cf6799f7faa1349 2 99999999999\n").is_err());
+
+		let (_, commit_info) = parse_commit_info("author-time\nauthor-tz\n\tcode\n").unwrap();
+		assert_eq!(commit_info.author_time, SystemTime::UNIX_EPOCH);
+	}
+
+	#[test]
+	fn parse_latin1() {
+		// this is what `git blame` hands back for a latin1 repo when nothing is re-encoded
+		let decoded = String::from_utf8_lossy(TEST_BLAME_OUTPUT_LATIN1);
+		let result = parse_blame_porcelain(&decoded).expect("couldn't parse blame");
+		assert_eq!(result.len(), 1);
+		assert_eq!(result[0].info.author, "Ren\u{FFFD} Fran\u{FFFD}ois");
+		assert_eq!(result[0].code, vec!["caf\u{FFFD}", "na\u{FFFD}ve"]);
 	}
 }
@@ -0,0 +1,221 @@
+//! column layout helpers shared by the blame list and anything else that renders blame lines.
+//! widths are counted in terminal columns, so emoji and fullwidth characters take 2 and combining marks take none.
+//! text is never split in the middle of a character
+
+use std::mem;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// how many columns a tab advances to, at most
+pub const TAB_WIDTH: usize = 4;
+
+/// the number of terminal columns `s` takes up
+pub fn width(s: &str) -> usize {
+	s.width()
+}
+
+/// replaces every tab with spaces up to the next tab stop, every [`TAB_WIDTH`] columns from the start of the line
+pub fn expand_tabs(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut column = 0;
+	for c in s.chars() {
+		match c {
+			'\t' => {
+				let spaces = TAB_WIDTH - column % TAB_WIDTH;
+				out.push_str(&" ".repeat(spaces));
+				column += spaces;
+			}
+			'\n' => {
+				out.push(c);
+				column = 0;
+			}
+			_ => {
+				out.push(c);
+				column += c.width().unwrap_or(0);
+			}
+		}
+	}
+	out
+}
+
+/// splits `s` after as many characters as fit in `width` columns, returning `(head, rest)`
+pub fn split_at_width(s: &str, width: usize) -> (&str, &str) {
+	let mut used = 0;
+	for (i, c) in s.char_indices() {
+		used += c.width().unwrap_or(0);
+		if used > width {
+			return s.split_at(i);
+		}
+	}
+	(s, "")
+}
+
+/// cuts `s` down to `width` columns, or pads it with spaces up to `width`, so the result is always exactly `width`
+/// columns wide. a wide character that would straddle the edge is replaced by padding
+pub fn fit_width(s: &str, width: usize) -> String {
+	let mut out = split_at_width(s, width).0.to_owned();
+	let used = out.width();
+	if used < width {
+		out.push_str(&" ".repeat(width - used));
+	}
+	out
+}
+
+/// like [`fit_width`], but padded on the left so `s` ends up against the right edge
+pub fn fit_width_right(s: &str, width: usize) -> String {
+	let fitted = fit_width(s, width);
+	let text = fitted.trim_end_matches(' ');
+	format!("{}{}", " ".repeat(fitted.len() - text.len()), text)
+}
+
+/// shortens `s` to at most `width` columns, ending it with `…` when anything was cut. unlike [`fit_width`], short
+/// strings are left unpadded
+pub fn truncate(s: &str, width: usize) -> String {
+	if s.width() <= width {
+		return s.to_owned();
+	}
+	let mut out = split_at_width(s, width.saturating_sub(1)).0.to_owned();
+	if width > 0 {
+		out.push('…');
+	}
+	out
+}
+
+/// the line number gutter: right-aligned in 4 columns with a space on either side
+pub fn line_number(line_num: i32) -> String {
+	format!(" {:4} ", line_num)
+}
+
+/// splits off trailing spaces and tabs, returning `(code, trailing_whitespace)`
+pub fn split_trailing_whitespace(s: &str) -> (&str, &str) {
+	let code = s.trim_end_matches([' ', '\t']);
+	s.split_at(code.len())
+}
+
+/// breaks `s` into rows of at most `width` columns at spaces, splitting words that don't fit in a row of their
+/// own. spaces are kept, so indentation survives on the first row
+pub fn wrap(s: &str, width: usize) -> Vec<String> {
+	if width == 0 || s.width() <= width {
+		return vec![s.to_owned()];
+	}
+	let mut rows = vec![];
+	let mut row = String::new();
+	let mut row_width = 0;
+	let mut row_started = false;
+	for word in s.split(' ') {
+		let word_width = word.width();
+		if row_started && row_width + 1 + word_width > width {
+			rows.push(mem::take(&mut row));
+			row_width = 0;
+			row_started = false;
+		}
+		if row_started {
+			row.push(' ');
+			row_width += 1;
+		}
+		let mut rest = word;
+		while row_width + rest.width() > width {
+			let (head, tail) = split_at_width(rest, width - row_width);
+			if head.is_empty() && row_width == 0 {
+				// a character wider than the whole row gets one to itself
+				let split = rest.chars().next().map_or(rest.len(), char::len_utf8);
+				rows.push(rest[..split].to_owned());
+				rest = &rest[split..];
+				continue;
+			}
+			row.push_str(head);
+			rows.push(mem::take(&mut row));
+			row_width = 0;
+			rest = tail;
+		}
+		row.push_str(rest);
+		row_width += rest.width();
+		row_started = true;
+	}
+	rows.push(row);
+	rows
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		expand_tabs, fit_width, fit_width_right, line_number, split_at_width, split_trailing_whitespace, truncate,
+		width, wrap,
+	};
+
+	#[test]
+	fn fit_width_truncates_and_pads() {
+		assert_eq!(fit_width("raylu", 3), "ray");
+		assert_eq!(fit_width("raylu", 8), "raylu   ");
+		assert_eq!(fit_width("raylu", 5), "raylu");
+		assert_eq!(fit_width("raylu", 0), "");
+		assert_eq!(fit_width("", 2), "  ");
+	}
+
+	#[test]
+	fn fit_width_multibyte() {
+		// padding is by characters, not bytes
+		assert_eq!(fit_width("José", 6), "José  ");
+		assert_eq!(fit_width("Zoë Smith", 3), "Zoë");
+		assert_eq!(fit_width("ＡＢＣ", 4), "ＡＢ");
+	}
+
+	#[test]
+	fn fit_width_wide() {
+		// fullwidth characters and emoji take 2 columns, combining marks none
+		assert_eq!(width("日本語"), 6);
+		assert_eq!(width("🦀 rust"), 7);
+		assert_eq!(width("e\u{301}"), 1);
+		assert_eq!(fit_width("日本語の名前", 4), "日本");
+		assert_eq!(fit_width("日本語の名前", 5), "日本 ");
+		assert_eq!(fit_width("🦀🦀", 6), "🦀🦀  ");
+		assert_eq!(fit_width_right("日本", 6), "  日本");
+		assert_eq!(split_at_width("a🦀b", 2), ("a", "🦀b"));
+		assert_eq!(split_at_width("a🦀b", 3), ("a🦀", "b"));
+	}
+
+	#[test]
+	fn fit_width_right_aligned() {
+		assert_eq!(fit_width_right("José", 6), "  José");
+		assert_eq!(fit_width_right("raylu", 3), "ray");
+		assert_eq!(fit_width_right("", 2), "  ");
+	}
+
+	#[test]
+	fn truncate_with_ellipsis() {
+		assert_eq!(truncate("short", 10), "short");
+		assert_eq!(truncate("a long summary", 6), "a lon…");
+		assert_eq!(truncate("ünïcödé", 4), "ünï…");
+		assert_eq!(truncate("anything", 0), "");
+		assert_eq!(truncate("日本語の要約", 6), "日本…");
+		assert_eq!(truncate("🦀 crab", 4), "🦀 …");
+	}
+
+	#[test]
+	fn gutter_and_whitespace() {
+		assert_eq!(line_number(7), "    7 ");
+		assert_eq!(line_number(12345), " 12345 ");
+		assert_eq!(expand_tabs("\tx\t"), "    x   ");
+		assert_eq!(expand_tabs("ab\tc\n\td"), "ab  c\n    d");
+		// tab stops are by column, so text before a tab lines up whatever it's written in
+		assert_eq!(expand_tabs("日本\tx"), "日本    x");
+		assert_eq!(expand_tabs("🦀\tx"), "🦀  x");
+		assert_eq!(split_trailing_whitespace("code \t "), ("code", " \t "));
+		assert_eq!(split_trailing_whitespace("code"), ("code", ""));
+	}
+
+	#[test]
+	fn wrap_at_spaces() {
+		assert_eq!(wrap("short", 10), ["short"]);
+		assert_eq!(wrap("", 10), [""]);
+		assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+		assert_eq!(wrap("  indented text here", 12), ["  indented", "text here"]);
+		assert_eq!(
+			wrap("a https://example.com/long", 10),
+			["a", "https://ex", "ample.com/", "long"]
+		);
+		assert_eq!(wrap("ünïcödé wörds", 7), ["ünïcödé", "wörds"]);
+		assert_eq!(wrap("日本語 テキスト", 8), ["日本語", "テキスト"]);
+		assert_eq!(wrap("🦀🦀🦀", 5), ["🦀🦀", "🦀"]);
+		assert_eq!(wrap("🦀x", 1), ["🦀", "x"]);
+	}
+}
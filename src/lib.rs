@@ -0,0 +1,10 @@
+//! the blame machinery behind the git-whence TUI: running `git blame --porcelain`,
+//! parsing its output ([`git_blame_porcelain`]) and turning it into renderable lines ([`git`])
+
+pub mod forge;
+pub mod fuzzy;
+pub mod git;
+pub mod git_blame_porcelain;
+pub mod layout;
+#[cfg(test)]
+mod temp_repo;
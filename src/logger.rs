@@ -0,0 +1,53 @@
+use std::{env, fs::File, io::Write, path::PathBuf, sync::Mutex, time::Instant};
+
+// the TUI owns the terminal, so debug output goes to a file instead
+struct FileLogger {
+	file: Mutex<File>,
+	start: Instant,
+}
+
+impl log::Log for FileLogger {
+	fn enabled(&self, _: &log::Metadata) -> bool {
+		true
+	}
+
+	fn log(&self, record: &log::Record) {
+		if let Ok(mut file) = self.file.lock() {
+			_ = writeln!(
+				file,
+				"{:>9.3}s {:5} {}",
+				self.start.elapsed().as_secs_f64(),
+				record.level(),
+				record.args()
+			);
+		}
+	}
+
+	fn flush(&self) {
+		if let Ok(mut file) = self.file.lock() {
+			_ = file.flush();
+		}
+	}
+}
+
+// logs to $GIT_WHENCE_LOG, or git-whence.log in the temp dir when only --debug was given.
+// returns the log path, or None when logging stays off and the log macros cost nothing
+pub fn init(debug: bool) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+	let path = match env::var_os("GIT_WHENCE_LOG") {
+		Some(path) if !path.is_empty() => PathBuf::from(path),
+		_ if debug => env::temp_dir().join("git-whence.log"),
+		_ => return Ok(None),
+	};
+	let file = File::options().create(true).append(true).open(&path)?;
+	log::set_boxed_logger(Box::new(FileLogger {
+		file: Mutex::new(file),
+		start: Instant::now(),
+	}))?;
+	log::set_max_level(log::LevelFilter::Debug);
+	log::info!(
+		"git-whence {} started at {}",
+		env!("CARGO_PKG_VERSION"),
+		chrono::Local::now()
+	);
+	Ok(Some(path))
+}
@@ -1,44 +1,369 @@
-use git2::Repository;
+use git2::{Oid, Repository};
+use git_whence::git;
 use std::{
 	env,
+	io::{self, IsTerminal, Read},
 	path::{Path, PathBuf},
+	process,
 };
 
-mod git;
-mod git_blame_porcelain;
+mod args;
+mod clipboard;
+mod completions;
+mod config;
+mod logger;
+mod pager;
+mod state;
+#[cfg(test)]
+mod temp_repo;
 mod terminal;
 
 fn main() {
-	let args: Vec<String> = env::args().collect();
-	if args.len() < 2 || args.len() > 3 {
-		println!("usage: {} <filepath> [rev]", args[0].rsplit('/').next().unwrap());
+	let argv: Vec<String> = env::args().collect();
+	let mut args = match args::parse(&argv[1..]) {
+		Ok(args) => args,
+		Err(e) => {
+			println!("{}", e);
+			println!("{}", args::usage(argv[0].rsplit('/').next().unwrap()));
+			return;
+		}
+	};
+	if args.version {
+		print_version();
 		return;
 	}
+	if let Some(shell) = &args.completions {
+		match completions::script(shell) {
+			Some(script) => print!("{}", script),
+			None => {
+				eprintln!("unsupported shell '{}'; expected bash, zsh or fish", shell);
+				process::exit(1);
+			}
+		}
+		return;
+	}
+
+	if let Err(e) = logger::init(args.debug) {
+		eprintln!("couldn't open the debug log: {}", e);
+		process::exit(1);
+	}
 
-	let path = Path::new(&args[1]);
-	let (repo, abs_path) = match path.canonicalize() {
-		Ok(abs_path) => (Repository::discover(&abs_path).unwrap(), abs_path),
-		Err(_) => (Repository::open_from_env().unwrap(), path_to_lexical_absolute(path)),
+	if let Some(rev) = &args.show {
+		show(rev, args.ignore_whitespace, args.abbrev);
+		return;
+	}
+
+	let first_path = Path::new(&args.paths[0]);
+	// the repository is found from the first path, or from the current directory when that path isn't on disk. like
+	// git, $GIT_DIR overrides both
+	let repo = match first_path.canonicalize() {
+		_ if env::var_os("GIT_DIR").is_some() => match Repository::open_from_env() {
+			Ok(repo) => repo,
+			Err(e) => exit_with_error(&format!("couldn't open $GIT_DIR: {}", e.message())),
+		},
+		Ok(abs_path) => match Repository::discover(&abs_path) {
+			Ok(repo) => repo,
+			Err(_) => exit_with_error(&format!("{} is not inside a git repository", first_path.display())),
+		},
+		Err(_) => match Repository::open_from_env() {
+			Ok(repo) => repo,
+			Err(_) => exit_with_error(&format!(
+				"no such file '{}' (and the current directory is not inside a git repository)",
+				first_path.display()
+			)),
+		},
 	};
-	let rel_path = abs_path.strip_prefix(repo.workdir().unwrap()).unwrap().to_owned();
 
-	let commit = if args.len() == 3 {
-		repo.revparse_single(&args[2]).unwrap().id()
-	} else {
-		repo.head().unwrap().target().unwrap()
+	// libgit2 only knows the working tree of a $GIT_DIR from $GIT_WORK_TREE or core.worktree. git falls back on the
+	// current directory, so do the same
+	if repo.workdir().is_none() && env::var_os("GIT_DIR").is_some() {
+		let cwd = env::current_dir()
+			.unwrap_or_else(|e| exit_with_error(&format!("couldn't find the current directory: {}", e)));
+		if let Err(e) = repo.set_workdir(&cwd, false) {
+			exit_with_error(&format!(
+				"couldn't use {} as the working tree: {}",
+				cwd.display(),
+				e.message()
+			));
+		}
+	}
+
+	// the last argument is taken for the rev when no file by that name is on disk, but it could also be a file that's
+	// only gone from the working tree, or a typo
+	if let Some(rev) = args
+		.rev
+		.as_deref()
+		.filter(|&rev| rev != "-" && !is_revision(&repo, rev))
+	{
+		let path = path_to_lexical_absolute(Path::new(args::split_line_number(rev).0));
+		let head = repo.head().ok().and_then(|head| head.target());
+		let in_head = repo
+			.workdir()
+			.and_then(|workdir| relative_to_workdir(&path, workdir))
+			.zip(head)
+			.is_some_and(|(rel_path, head)| git::path_exists(&repo, &rel_path, head));
+		if !in_head {
+			exit_with_error(&format!("'{}' is neither a file nor a revision", rev));
+		}
+		args.paths.extend(args.rev.take());
+	}
+
+	// `-` reads the rev from stdin, as in `git rev-parse HEAD~3 | git whence file.rs -`
+	let rev = args.rev.as_deref().map(|rev| match rev {
+		"-" => read_rev_from_stdin().unwrap_or_else(|e| exit_with_error(&e)),
+		rev => rev.to_owned(),
+	});
+	let mut commit = match &rev {
+		Some(rev) => resolve_rev(&repo, rev),
+		// a new repository's HEAD names a branch with no commits yet
+		None => repo
+			.head()
+			.ok()
+			.and_then(|head| head.target())
+			.unwrap_or_else(|| exit_with_error("HEAD does not point to a commit; pass a revision")),
+	};
+	if let Some(until) = &args.until {
+		commit = match git::last_commit_until(&repo, commit, until) {
+			Ok(Some(commit)) => commit,
+			Ok(None) => exit_with_error(&format!("no commits until {}", until)),
+			Err(e) => exit_with_error(e.to_string().trim_end()),
+		};
+	}
+	let rev_name = match &rev {
+		_ if args.staged => "the index",
+		Some(rev) => rev,
+		None => "HEAD",
 	};
-	let mut app = terminal::App::new(&repo, &rel_path, commit);
-	app.blame = match git::blame(&repo, &rel_path, commit) {
-		Ok(blame) => blame,
-		Err(e) => panic!("{}", e),
+	// the null commit stands for the index
+	let start = if args.staged { Oid::zero() } else { commit };
+	let files: Vec<(PathBuf, Option<PathBuf>, Option<usize>, Oid)> = args
+		.paths
+		.iter()
+		.map(|path| {
+			let (path, line) = args::split_line_number(path);
+			let (rel_path, followed_from) = resolve_path(&repo, Path::new(path), start, rev_name, args.follow);
+			// `git blame` without a rev blames what's in the working tree; the closest thing with a commit to go back
+			// from is what's staged
+			let implicit = rev.is_none() && args.until.is_none();
+			let start = if implicit && git::has_staged_changes(&repo, &rel_path) {
+				Oid::zero()
+			} else {
+				start
+			};
+			(rel_path, followed_from, line, start)
+		})
+		.collect();
+	let mut config = config::Config::load(&repo);
+	if args.no_alt_screen {
+		config.alt_screen = false;
+	}
+	if let Some(abbrev) = args.abbrev {
+		config.abbrev = abbrev;
+	}
+	let blame_options = git::BlameOptions {
+		encoding: args.encoding.or(config.encoding.clone()),
+		quote_path: config.quote_path,
+		since: args.since,
+		ignore_whitespace: args.ignore_whitespace || config.ignore_whitespace,
+		detect_moves: args.detect_moves || config.detect_moves,
+		detect_copies: args.detect_copies || config.detect_copies,
+		first_parent: args.first_parent || config.first_parent,
+		root: args.root || config.root,
+		ignore_revs_file: args.ignore_revs_file.or(config.ignore_revs_file.clone()),
+		ignore_revs: vec![],
 	};
-	let mut term = terminal::setup().unwrap();
-	let res = terminal::run_app(&mut term, app);
+	if args.porcelain {
+		for (rel_path, _, _, start) in &files {
+			let (blame, warning) = git::blame_with_warnings(&repo, rel_path, *start, &blame_options)
+				.unwrap_or_else(|e| exit_with_error(e.to_string().trim_end()));
+			// on stderr like git's own, out of the way of scripts reading stdout
+			if let Some(warning) = warning {
+				eprintln!("{}", warning);
+			}
+			for hunk in &blame {
+				println!("{}", git::format_porcelain_line(hunk, rel_path));
+			}
+		}
+		return;
+	}
+	let mut app = terminal::App::new(&repo, &config, blame_options);
+	for (rel_path, followed_from, line, start) in files {
+		if let Err(e) = app.open_tab(&rel_path, start, followed_from) {
+			exit_with_error(e.to_string().trim_end());
+		}
+		if let Some(line) = line {
+			app.go_to_line(line);
+		}
+		// a rev on the command line says where to look, so there's nothing to offer
+		if config.remember_rev && rev.is_none() {
+			let remembered = state::last_rev(repo.path(), &rel_path);
+			if let Some(remembered) = remembered.filter(|&old| old != start && repo.find_commit(old).is_ok()) {
+				app.offer_remembered_rev(remembered);
+			}
+		}
+	}
+	app.switch_tab(0);
+	if let Some(compare) = &args.compare {
+		if let Err(e) = app.compare_with(resolve_rev(&repo, compare)) {
+			exit_with_error(&format!("couldn't blame at {}: {}", compare, e.to_string().trim_end()));
+		}
+	}
+	let mut term = terminal::setup(config.mouse, config.alt_screen).unwrap();
+	let res = terminal::run_app(&mut term, &mut app);
 
-	terminal::teardown(&mut term);
+	terminal::teardown(&mut term, config.alt_screen);
 	if let Err(err) = res {
 		println!("{:?}", err)
 	}
+	if config.remember_rev {
+		if let Err(e) = state::save(repo.path(), &app.tab_revisions()) {
+			eprintln!("couldn't remember where files were blamed: {}", e);
+		}
+	}
+}
+
+// --show: the commit as `enter` shows it, for scripts and a quick look without the TUI
+fn show(rev: &str, ignore_whitespace: bool, abbrev: Option<usize>) {
+	let repo = Repository::open_from_env()
+		.unwrap_or_else(|_| exit_with_error("the current directory is not inside a git repository"));
+	let commit = resolve_rev(&repo, rev);
+	let config = config::Config::load(&repo);
+	let color = io::stdout().is_terminal() && git::color_enabled(&repo);
+	// the message body wraps to the terminal like it does to the panel
+	let width = crossterm::terminal::size().map_or(80, |(width, _)| usize::from(width));
+	let view = git::show(
+		&repo,
+		commit,
+		1,
+		color,
+		ignore_whitespace || config.ignore_whitespace,
+		width,
+		abbrev.unwrap_or(config.abbrev),
+	);
+	if let Err(e) = pager::page(&git::text_to_ansi(&view.text, color)) {
+		exit_with_error(&format!("couldn't write the commit: {}", e));
+	}
+}
+
+fn is_revision(repo: &Repository, rev: &str) -> bool {
+	repo.revparse_single(rev).and_then(|obj| obj.peel_to_commit()).is_ok()
+}
+
+fn resolve_rev(repo: &Repository, rev: &str) -> git2::Oid {
+	match repo.revparse_single(rev).and_then(|obj| obj.peel_to_commit()) {
+		Ok(commit) => commit.id(),
+		Err(_) => {
+			eprintln!("unknown revision '{}'", rev);
+			eprintln!("expected a branch (main, origin/main), a tag (v1.0) or a commit sha (1a2b3c4d)");
+			eprintln!("see `git branch -a`, `git tag` or `git log --oneline` for valid revisions");
+			process::exit(1);
+		}
+	}
+}
+
+// the path relative to the workdir and, when --follow found an older name, the path as given
+fn resolve_path(
+	repo: &Repository,
+	path: &Path,
+	commit: git2::Oid,
+	rev_name: &str,
+	follow: bool,
+) -> (PathBuf, Option<PathBuf>) {
+	// a path that doesn't exist on disk may still exist at the requested revision, so only check that once it's known
+	let (abs_path, on_disk) = match path.canonicalize() {
+		Ok(abs_path) => (abs_path, true),
+		Err(_) => (path_to_lexical_absolute(path), false),
+	};
+	let Some(workdir) = repo.workdir() else {
+		exit_with_error(&format!(
+			"{} is a bare repository, so there's no working tree to find '{}' in; set $GIT_DIR and $GIT_WORK_TREE to blame its files",
+			repo.path().display(),
+			path.display()
+		));
+	};
+	let rel_path = match relative_to_workdir(&abs_path, workdir) {
+		Some(rel_path) => rel_path,
+		None if !on_disk => exit_with_error(&format!("no such file '{}'", path.display())),
+		None => exit_with_error(&format!(
+			"{} is outside the repository's working tree ({})",
+			abs_path.display(),
+			workdir.display()
+		)),
+	};
+	if git::path_exists(repo, &rel_path, commit) {
+		return (rel_path, None);
+	}
+	let historical_path = if follow {
+		git::follow_path(repo, &rel_path, commit).unwrap_or_else(|e| exit_with_error(&e.to_string()))
+	} else {
+		None
+	};
+	match historical_path {
+		Some(historical_path) => (historical_path, Some(rel_path)),
+		None if !on_disk => exit_with_error(&format!(
+			"no such file '{}' in the working tree or at {}",
+			path.display(),
+			rev_name
+		)),
+		None if follow => exit_with_error(&format!(
+			"couldn't find what '{}' was called at {} by following it back from HEAD",
+			path.display(),
+			rev_name
+		)),
+		None => exit_with_error(&format!(
+			"'{}' doesn't exist at {}; if it was renamed since, try --follow",
+			path.display(),
+			rev_name
+		)),
+	}
+}
+
+fn exit_with_error(message: &str) -> ! {
+	eprintln!("{}", message);
+	process::exit(1);
+}
+
+fn read_rev_from_stdin() -> Result<String, String> {
+	let mut stdin = io::stdin();
+	if stdin.is_terminal() {
+		return Err("rev '-' reads a revision from stdin, but stdin is a terminal; pipe one in".to_owned());
+	}
+	let mut input = String::new();
+	stdin
+		.read_to_string(&mut input)
+		.map_err(|e| format!("couldn't read a revision from stdin: {}", e))?;
+	let mut words = input.split_whitespace();
+	match (words.next(), words.next()) {
+		(Some(rev), None) => Ok(rev.to_owned()),
+		(None, _) => Err("rev '-' reads a revision from stdin, but stdin was empty".to_owned()),
+		(Some(_), Some(_)) => Err(format!("expected a single revision on stdin, got '{}'", input.trim())),
+	}
+}
+
+fn print_version() {
+	println!("git-whence {}", env!("CARGO_PKG_VERSION"));
+	let libgit2 = git2::Version::get();
+	let (major, minor, patch) = libgit2.libgit2_version();
+	println!(
+		"libgit2 {}.{}.{} ({})",
+		major,
+		minor,
+		patch,
+		if libgit2.vendored() { "bundled" } else { "system" }
+	);
+	match process::Command::new("git").arg("--version").output() {
+		Ok(output) if output.status.success() => print!("{}", String::from_utf8_lossy(&output.stdout)),
+		_ => println!("git not found"),
+	}
+}
+
+// the workdir may itself be reached through a symlink, so try both the path git reported and its canonical form
+fn relative_to_workdir(abs_path: &Path, workdir: &Path) -> Option<PathBuf> {
+	if let Ok(rel_path) = abs_path.strip_prefix(workdir) {
+		return Some(rel_path.to_owned());
+	}
+	let canonical_workdir = workdir.canonicalize().ok()?;
+	abs_path.strip_prefix(canonical_workdir).ok().map(Path::to_owned)
 }
 
 fn path_to_lexical_absolute(path: &Path) -> PathBuf {
@@ -0,0 +1,38 @@
+use std::{
+	env,
+	io::{self, IsTerminal, Write},
+	process,
+};
+
+// writes `output` through the pager git would use (core.pager, $GIT_PAGER, $PAGER, less), or straight to stdout when
+// that isn't a terminal or there's no pager to run
+pub fn page(output: &str) -> io::Result<()> {
+	let pager = if io::stdout().is_terminal() { pager() } else { None };
+	let Some(pager) = pager else {
+		let mut stdout = io::stdout().lock();
+		return stdout.write_all(output.as_bytes()).and_then(|()| stdout.flush());
+	};
+	let mut command = process::Command::new("sh");
+	command.args(["-c", &pager]).stdin(process::Stdio::piped());
+	// like git: quit if it fits on one screen, keep colors and don't clear the screen
+	if env::var_os("LESS").is_none() {
+		command.env("LESS", "FRX");
+	}
+	if env::var_os("LV").is_none() {
+		command.env("LV", "-c");
+	}
+	let mut child = command.spawn()?;
+	// the pager quitting early closes the pipe, which isn't worth reporting
+	match child.stdin.take().unwrap().write_all(output.as_bytes()) {
+		Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+		_ => {}
+	}
+	child.wait()?;
+	Ok(())
+}
+
+fn pager() -> Option<String> {
+	let output = process::Command::new("git").args(["var", "GIT_PAGER"]).output().ok()?;
+	let pager = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+	(output.status.success() && !pager.is_empty() && pager != "cat").then_some(pager)
+}
@@ -0,0 +1,95 @@
+use git2::Oid;
+use std::{
+	env, fs, io,
+	path::{Path, PathBuf},
+};
+
+// how many files' revisions are kept, most recent first
+const MAX_ENTRIES: usize = 1000;
+
+// the revision each file was last blamed at, kept across runs when `whence.rememberRev` is set. one
+// `<commit>\t<repo>\t<path>` line per file in $XDG_CACHE_HOME/git-whence/last-rev
+fn state_path() -> Option<PathBuf> {
+	let cache_dir = match env::var_os("XDG_CACHE_HOME") {
+		Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+		_ => PathBuf::from(env::var_os("HOME").filter(|home| !home.is_empty())?).join(".cache"),
+	};
+	Some(cache_dir.join("git-whence").join("last-rev"))
+}
+
+pub fn last_rev(repo_dir: &Path, rel_path: &Path) -> Option<Oid> {
+	let contents = fs::read_to_string(state_path()?).ok()?;
+	let (repo_dir, rel_path) = (repo_dir.to_str()?, rel_path.to_str()?);
+	let mut entries = parse(&contents);
+	entries.find_map(|(commit, repo, path)| (repo == repo_dir && path == rel_path).then_some(commit))
+}
+
+// records `revs` (path and commit) for the repository at `repo_dir`, replacing whatever was remembered for them.
+// paths without a commit are forgotten
+pub fn save(repo_dir: &Path, revs: &[(PathBuf, Option<Oid>)]) -> io::Result<()> {
+	let Some(path) = state_path() else {
+		return Ok(());
+	};
+	let Some(repo_dir) = repo_dir.to_str() else {
+		return Ok(());
+	};
+	let existing = fs::read_to_string(&path).unwrap_or_default();
+	let updates: Vec<(Option<Oid>, &str, &str)> = revs
+		.iter()
+		.filter_map(|(rel_path, commit)| Some((*commit, repo_dir, rel_path.to_str()?)))
+		.collect();
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	fs::write(path, merge(&existing, &updates))
+}
+
+fn parse(contents: &str) -> impl Iterator<Item = (Oid, &str, &str)> {
+	contents.lines().filter_map(|line| {
+		let mut fields = line.splitn(3, '\t');
+		let commit = Oid::from_str(fields.next()?).ok()?;
+		Some((commit, fields.next()?, fields.next()?))
+	})
+}
+
+fn merge(existing: &str, updates: &[(Option<Oid>, &str, &str)]) -> String {
+	let kept = parse(existing).filter(|&(_, repo, path)| {
+		!updates
+			.iter()
+			.any(|&(_, update_repo, update_path)| repo == update_repo && path == update_path)
+	});
+	updates
+		.iter()
+		.filter_map(|&(commit, repo, path)| Some((commit?, repo, path)))
+		.chain(kept)
+		.take(MAX_ENTRIES)
+		.map(|(commit, repo, path)| format!("{}\t{}\t{}\n", commit, repo, path))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::merge;
+	use git2::Oid;
+
+	#[test]
+	fn merge_replaces_entries() {
+		let old = Oid::from_str("1111111111111111111111111111111111111111").unwrap();
+		let new = Oid::from_str("2222222222222222222222222222222222222222").unwrap();
+		let existing = format!("{}\t/r/.git/\ta.rs\n{}\t/r/.git/\tb.rs\nnot a commit\tx\ty\n", old, old);
+		assert_eq!(
+			merge(&existing, &[(Some(new), "/r/.git/", "b.rs")]),
+			format!("{}\t/r/.git/\tb.rs\n{}\t/r/.git/\ta.rs\n", new, old)
+		);
+	}
+
+	#[test]
+	fn merge_forgets_entries_back_at_head() {
+		let old = Oid::from_str("1111111111111111111111111111111111111111").unwrap();
+		let existing = format!("{}\t/r/.git/\ta.rs\n{}\t/r/.git/\tb.rs\n", old, old);
+		assert_eq!(
+			merge(&existing, &[(None, "/r/.git/", "b.rs"), (None, "/r/.git/", "c.rs")]),
+			format!("{}\t/r/.git/\ta.rs\n", old)
+		);
+	}
+}
@@ -0,0 +1,73 @@
+// shared by the library's tests and the binary's, which each use only some of it
+#![allow(dead_code)]
+
+use git2::{Oid, Repository};
+use std::{env, fs, path::PathBuf, process};
+
+// a repository in the temp dir, deleted when dropped so a failed assertion doesn't leave it behind
+pub struct TempRepo {
+	pub dir: PathBuf,
+	pub repo: Repository,
+}
+
+impl TempRepo {
+	pub fn new(name: &str) -> TempRepo {
+		let dir = env::temp_dir().join(format!("git-whence-test-{}-{}", name, process::id()));
+		let repo = Repository::init(&dir).unwrap();
+		TempRepo { dir, repo }
+	}
+
+	// writes `path` and commits it on top of `parents`, or on HEAD when there are none
+	pub fn commit(&self, path: &str, contents: &str, message: &str, parents: &[Oid]) -> Oid {
+		self.commit_as("raylu", path, contents, message, parents)
+	}
+
+	pub fn commit_as(&self, author: &str, path: &str, contents: &str, message: &str, parents: &[Oid]) -> Oid {
+		fs::write(self.dir.join(path), contents).unwrap();
+		let mut index = self.repo.index().unwrap();
+		index.add_path(path.as_ref()).unwrap();
+		index.write().unwrap();
+		let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+		let signature = git2::Signature::now(author, "raylu@example.com").unwrap();
+		let parents: Vec<git2::Commit> = if parents.is_empty() {
+			self.repo
+				.head()
+				.ok()
+				.and_then(|head| head.peel_to_commit().ok())
+				.into_iter()
+				.collect()
+		} else {
+			parents
+				.iter()
+				.map(|&parent| self.repo.find_commit(parent).unwrap())
+				.collect()
+		};
+		let parents: Vec<&git2::Commit> = parents.iter().collect();
+		let commit = self
+			.repo
+			.commit(None, &signature, &signature, message, &tree, &parents)
+			.unwrap();
+		self.repo.set_head_detached(commit).unwrap();
+		commit
+	}
+
+	// commits everything in the worktree on top of HEAD, including files that were removed or renamed away
+	pub fn commit_all(&self, message: &str) -> Oid {
+		let mut index = self.repo.index().unwrap();
+		index.add_all(["*"], git2::IndexAddOption::FORCE, None).unwrap();
+		index.update_all(["*"], None).unwrap();
+		let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+		let signature = git2::Signature::now("raylu", "raylu@example.com").unwrap();
+		let parent = self.repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+		let parents: Vec<&git2::Commit> = parent.iter().collect();
+		self.repo
+			.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+			.unwrap()
+	}
+}
+
+impl Drop for TempRepo {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.dir);
+	}
+}
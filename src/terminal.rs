@@ -1,39 +1,153 @@
 use crossterm::{
 	event::{
-		self, Event,
+		self, DisableMouseCapture, EnableMouseCapture, Event,
 		KeyCode::{self, Char},
-		KeyEvent, KeyModifiers,
+		KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 	},
 	execute,
 	terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use git2::{Oid, Repository};
 use std::{
+	cell::{Cell, RefCell},
+	collections::{HashMap, VecDeque},
 	error::Error,
-	io::{self, Stdout},
+	fs,
+	io::{self, Stdout, Write},
+	mem,
+	panic::{self, AssertUnwindSafe},
 	path::{Path, PathBuf},
+	time::{Duration, SystemTime},
 };
 use tui::{
-	backend::CrosstermBackend,
-	layout::{Constraint, Direction, Layout, Rect},
+	backend::{Backend, CrosstermBackend},
+	buffer,
+	layout::{Alignment, Constraint, Direction, Layout, Rect},
 	style::{Color, Modifier, Style},
 	text::{Line, Span, Text},
-	widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+	widgets::{
+		Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+		Wrap,
+	},
 	Frame, Terminal,
 };
 
-use crate::git;
+use git_whence::forge::{Remote, Templates};
+
+use crate::{
+	clipboard,
+	config::{Config, ScrollAmount},
+};
+use git_whence::{
+	git::{self, Column},
+	layout,
+};
+
+const FULL_HASH_WIDTH: usize = 40;
+const FOOTER_HEIGHT: u16 = 2;
+// how many rendered commits are kept before starting over
+const SHOWN_CACHE_SIZE: usize = 64;
+// how many places ctrl-o can go back through, like vim's
+const JUMP_LIST_SIZE: usize = 100;
+// how long formatted lines are drawn again as they are, so relative times still keep up
+const FORMATTED_MAX_AGE: Duration = Duration::from_secs(1);
 
 pub struct App<'a> {
-	pub blame: Vec<git::BlameHunk<'a>>,
+	blame: Vec<git::BlameHunk>,
 	blame_state: ListState,
 	repo: &'a Repository,
-	commit_stack: Vec<CommitPath>,      // pushed by `b`, popped by `B`
-	right_panel: Option<Text<'static>>, // activated by `w` or <enter>
+	commit_stack: Vec<CommitPath>,       // pushed by `b`, popped by `B`
+	right_panel: Option<Text<'static>>,  // activated by `w` or <enter>, and set with `set_panel`
+	wrapped_panel: Option<WrappedPanel>, // the panel broken into rows for whence.panelWrap as last drawn
 	line_history_scroll: u16,
+	panel_x_scroll: u16, // columns the panel is scrolled right by →, for lines too wide for it
+	shown_commit: Option<ShownCommit>, // set while the right panel is showing a commit from <enter>
+	file_view: Option<Vec<String>>, // plain file content instead of blame, toggled by `v`
 	popup: Option<Text<'static>>,
+	help: Option<HelpView>, // set while the popup is the help, which can scroll and fill the screen
+	abbrev: usize,          // hash width when `H` isn't showing full hashes
+	footer: bool,           // details of the selected line under the blame, toggled by `F`
+	panel_wrap: bool,       // break long lines in the panel instead of cutting them off
+	// rendered commits by id, parent, whether whitespace is ignored and panel width, so showing one again is instant
+	shown_cache: HashMap<(Oid, usize, bool, u16), git::CommitView>,
+	// the blame (or file view) lines as last drawn, so each key press doesn't format the whole file again
+	formatted: Option<FormattedLines>,
 	search: Option<Search>,
-	line_number: Option<String>,
+	// the selected line when `:` was pressed, which esc goes back to
+	command_origin: Option<usize>,
+	command: Option<String>,     // typed after `:`, a line number or `find <text>`
+	open_path: Option<String>,   // path being typed after `o`
+	picker: Option<Picker>,      // the fuzzy file finder from ctrl-p
+	settings: Option<ListState>, // the settings menu from `s`, with the selected setting
+	status: Option<String>,      // one-off message for the status line, cleared by the next key press
+	pending_key: Option<char>,   // first key of a two-key command like `ma`
+	marks: HashMap<char, usize>, // set by `m<letter>`, jumped to by `'<letter>`
+	jumps: JumpList,
+	color: bool,
+	columns: git::Columns,
+	blame_options: git::BlameOptions,
+	auto_author_width: bool,
+	scroll_amount: ScrollAmount,
+	search_wrap: bool,
+	scrollbar: bool,
+	split: u16,           // the blame's share of the width in percent while the panel is open, changed by `<`/`>`
+	mouse: bool,          // whence.mouse
+	alt_screen: bool,     // whence.altScreen, for getting the terminal back after ctrl-z
+	divider: Option<u16>, // the panel's left edge as last drawn, for dragging it with the mouse
+	dragging_divider: bool, // between a mouse press on the divider and its release
+	refresh: Option<Duration>, // how often to redraw so relative times keep up
+	panel_width: u16,     // what shown commit messages are wrapped to, kept up to date by `fit_panel`
+	selection: Style,
+	link_remote: Option<Remote>,    // where commit hashes link to, when whence.hyperlinks is on
+	url_templates: Templates,       // whence.commitUrl and whence.permalinkUrl, for `yl`
+	links: Vec<Link>,               // the hashes on screen, linked after each draw
+	ignore_whitespace: bool,        // in shown commits, toggled by ctrl-w
+	followed_from: Option<PathBuf>, // the path given on the command line when --follow found an older name
+	remembered_rev: Option<Oid>,    // where the file was last blamed in an earlier run, reopened by `gr`
+	tabs: Vec<Tab>,
+	active_tab: usize,
+}
+
+// commit hashes are turned into OSC 8 hyperlinks by redrawing their cells wrapped in the escape sequence after
+// ratatui has drawn the frame. ratatui would count the escape sequence's characters as columns if they were in a span
+struct Link {
+	x: u16,
+	y: u16,
+	cells: Vec<buffer::Cell>, // as drawn, so the link keeps the hash's colors and the selection
+	url: String,
+}
+
+struct FormattedLines {
+	columns: git::Columns, // what they were formatted with
+	at: SystemTime,
+	lines: Vec<Line<'static>>,
+	widths: Vec<usize>,
+}
+
+struct WrappedPanel {
+	width: u16, // what it was wrapped to
+	lines: Vec<Line<'static>>,
+	starts: Vec<usize>, // the row each of the panel's lines starts on
+}
+
+#[derive(Default)]
+struct HelpView {
+	scroll: u16,
+	full_screen: bool,
+}
+
+// the state of one open file. the active tab's state lives in `App` and is only saved here when switching away,
+// so `tabs[active_tab]` is stale until then
+#[derive(Default)]
+struct Tab {
+	blame: Vec<git::BlameHunk>,
+	blame_state: ListState,
+	commit_stack: Vec<CommitPath>,
+	file_view: Option<Vec<String>>,
+	marks: HashMap<char, usize>,
+	jumps: JumpList,
+	followed_from: Option<PathBuf>,
+	remembered_rev: Option<Oid>,
 }
 
 struct Search {
@@ -41,69 +155,940 @@ struct Search {
 	query: String,
 }
 
+// somewhere the jump list can go back to: the revisions on the stack and the line selected in the top one
+#[derive(Clone)]
+struct Place {
+	commit_stack: Vec<CommitPath>,
+	selected: Option<usize>,
+}
+
+impl Place {
+	fn same_as(&self, other: &Place) -> bool {
+		self.selected == other.selected
+			&& self.commit_stack.len() == other.commit_stack.len()
+			&& self
+				.commit_stack
+				.iter()
+				.zip(&other.commit_stack)
+				.all(|(a, b)| a.commit == b.commit && a.path == b.path)
+	}
+}
+
+// vim's jump list: where the selection was before each jump (`G`, searches, marks, reblames...), for ctrl-o and
+// ctrl-n to go back and forth through
+#[derive(Default)]
+struct JumpList {
+	places: VecDeque<Place>,
+	position: usize, // the place last gone back or forward to, or `places.len()` when not going through the list
+}
+
+impl JumpList {
+	// a new jump forgets the places ctrl-o went back past, like starting a new branch of undo history
+	fn record(&mut self, place: Place) {
+		self.places.truncate(self.position);
+		if self.places.back().is_some_and(|last| last.same_as(&place)) {
+			self.places.pop_back();
+		}
+		self.places.push_back(place);
+		if self.places.len() > JUMP_LIST_SIZE {
+			self.places.pop_front();
+		}
+		self.position = self.places.len();
+	}
+
+	// going back from the newest place remembers `here` first, so ctrl-n can return to it
+	fn back(&mut self, here: Place) -> Option<Place> {
+		if self.position == self.places.len() {
+			self.record(here);
+			self.position -= 1;
+		}
+		self.position = self.position.checked_sub(1)?;
+		Some(self.places[self.position].clone())
+	}
+
+	fn forward(&mut self) -> Option<Place> {
+		if self.position + 1 >= self.places.len() {
+			return None;
+		}
+		self.position += 1;
+		Some(self.places[self.position].clone())
+	}
+}
+
+// one line of the settings menu
+struct Setting {
+	name: &'static str,
+	config_key: Option<&'static str>, // where `w` in the menu saves it, if anywhere
+	get: fn(&App) -> bool,
+	set: fn(&mut App, bool),
+}
+
+const SETTINGS: &[Setting] = &[
+	Setting {
+		name: "ignore whitespace (blame -w)",
+		config_key: Some("whence.ignoreWhitespace"),
+		get: |app| app.blame_options.ignore_whitespace,
+		set: |app, on| {
+			app.blame_options.ignore_whitespace = on;
+			app.reblame();
+		},
+	},
+	Setting {
+		name: "detect lines moved within the file (blame -M)",
+		config_key: Some("whence.detectMoves"),
+		get: |app| app.blame_options.detect_moves,
+		set: |app, on| {
+			app.blame_options.detect_moves = on;
+			app.reblame();
+		},
+	},
+	Setting {
+		name: "detect lines from other files (blame -C)",
+		config_key: Some("whence.detectCopies"),
+		get: |app| app.blame_options.detect_copies,
+		set: |app, on| {
+			app.blame_options.detect_copies = on;
+			app.reblame();
+		},
+	},
+	Setting {
+		name: "follow only the first parent of merges",
+		config_key: Some("whence.firstParent"),
+		get: |app| app.blame_options.first_parent,
+		set: |app, on| {
+			app.blame_options.first_parent = on;
+			app.reblame();
+		},
+	},
+	Setting {
+		name: "blame the root commit instead of marking it ^",
+		config_key: None, // saving would change plain `git blame` too
+		get: |app| app.blame_options.root,
+		set: |app, on| {
+			app.blame_options.root = on;
+			app.reblame();
+		},
+	},
+	Setting {
+		name: "hash column",
+		config_key: None,
+		get: |app| app.columns.show_hash,
+		set: |app, on| app.columns.show_hash = on,
+	},
+	Setting {
+		name: "full commit hashes",
+		config_key: Some("whence.fullHash"),
+		get: |app| app.columns.hash_width == FULL_HASH_WIDTH,
+		set: |app, on| app.columns.hash_width = if on { FULL_HASH_WIDTH } else { app.abbrev },
+	},
+	Setting {
+		name: "author column",
+		config_key: None,
+		get: |app| app.columns.show_author,
+		set: |app, on| app.columns.show_author = on,
+	},
+	Setting {
+		name: "author initials instead of names",
+		config_key: Some("whence.authorInitials"),
+		get: |app| app.columns.author_initials,
+		set: |app, on| app.columns.author_initials = on,
+	},
+	Setting {
+		name: "right-align author names",
+		config_key: None, // whence.authorAlign isn't a boolean
+		get: |app| app.columns.right_align_author,
+		set: |app, on| app.columns.right_align_author = on,
+	},
+	Setting {
+		name: "time column",
+		config_key: None,
+		get: |app| app.columns.show_time,
+		set: |app, on| app.columns.show_time = on,
+	},
+	Setting {
+		name: "dates instead of relative times for old commits",
+		config_key: None,
+		get: |app| app.columns.human_dates,
+		set: |app, on| app.columns.human_dates = on,
+	},
+	Setting {
+		name: "mark rewritten commits with *",
+		config_key: None,
+		get: |app| app.columns.mark_rewritten,
+		set: |app, on| app.columns.mark_rewritten = on,
+	},
+	Setting {
+		name: "mark merge commits with ᴹ",
+		config_key: Some("whence.markMerges"),
+		get: |app| app.columns.mark_merges,
+		set: |app, on| app.columns.mark_merges = on,
+	},
+	Setting {
+		name: "footer with details of the selected line",
+		config_key: Some("whence.footer"),
+		get: |app| app.footer,
+		set: |app, on| app.footer = on,
+	},
+	Setting {
+		name: "wrap long lines in the panel",
+		config_key: Some("whence.panelWrap"),
+		get: |app| app.panel_wrap,
+		set: |app, on| app.panel_wrap = on,
+	},
+	Setting {
+		name: "line number column",
+		config_key: None,
+		get: |app| app.columns.show_line_num,
+		set: |app, on| app.columns.show_line_num = on,
+	},
+	Setting {
+		name: "summary column",
+		config_key: None,
+		get: |app| app.columns.show_summary,
+		set: |app, on| app.columns.show_summary = on,
+	},
+	Setting {
+		name: "highlight trailing whitespace",
+		config_key: None,
+		get: |app| app.columns.highlight_trailing_whitespace,
+		set: |app, on| app.columns.highlight_trailing_whitespace = on,
+	},
+	Setting {
+		name: "mark blank lines with ~",
+		config_key: Some("whence.markBlankLines"),
+		get: |app| app.columns.mark_blank_lines,
+		set: |app, on| app.columns.mark_blank_lines = on,
+	},
+	Setting {
+		name: "scrollbars",
+		config_key: Some("whence.scrollbar"),
+		get: |app| app.scrollbar,
+		set: |app, on| app.scrollbar = on,
+	},
+	Setting {
+		name: "search wraps around",
+		config_key: Some("whence.searchWrap"),
+		get: |app| app.search_wrap,
+		set: |app, on| app.search_wrap = on,
+	},
+];
+
+struct Picker {
+	query: String,
+	files: Vec<String>,
+	matches: Vec<usize>, // indices into `files`, best first
+	state: ListState,
+}
+
+impl Picker {
+	fn new(files: Vec<String>) -> Picker {
+		let mut picker = Picker {
+			query: String::new(),
+			matches: vec![],
+			files,
+			state: ListState::default(),
+		};
+		picker.refilter();
+		picker
+	}
+
+	fn refilter(&mut self) {
+		self.matches = git_whence::fuzzy::filter(&self.query, &self.files);
+		self.state.select((!self.matches.is_empty()).then_some(0));
+	}
+
+	fn select(&mut self, offset: isize) {
+		if let Some(index) = self.state.selected() {
+			let last = self.matches.len() - 1;
+			self.state.select(Some(index.saturating_add_signed(offset).min(last)));
+		}
+	}
+}
+
+struct ShownCommit {
+	commit: Oid,
+	parent: usize, // which parent the diff is against; 0 is the combined diff of a merge
+	locations: Vec<Option<git::DiffLocation>>, // the file line behind each line of the panel
+}
+
+#[derive(Clone)]
 struct CommitPath {
 	commit: Oid,
 	path: PathBuf,
+	blame_state: ListState, // where the list was when `b` left this revision, restored by `B`
+	from_shown: bool,       // pushed from a shown commit by `L` or `R`
 }
 
 impl App<'_> {
-	pub fn new<'a>(repo: &'a Repository, rel_path: &'a Path, commit: Oid) -> App<'a> {
+	// starts with no files; open at least one with `open_tab`
+	pub fn new<'a>(repo: &'a Repository, config: &Config, blame_options: git::BlameOptions) -> App<'a> {
+		// with `whence.columns` set, only the columns it lists start out shown
+		let shown = |column, default| {
+			config
+				.columns
+				.as_ref()
+				.map_or(default, |columns| columns.contains(&column))
+		};
 		App {
 			blame: vec![],
 			blame_state: ListState::default(),
 			repo,
-			commit_stack: vec![CommitPath {
-				commit,
-				path: rel_path.to_owned(),
-			}],
+			commit_stack: vec![],
 			right_panel: None,
+			wrapped_panel: None,
 			line_history_scroll: 0,
+			panel_x_scroll: 0,
+			shown_commit: None,
+			file_view: None,
 			popup: None,
+			help: None,
+			abbrev: config.abbrev,
+			footer: config.footer,
+			panel_wrap: config.panel_wrap,
+			shown_cache: HashMap::new(),
+			formatted: None,
 			search: None,
-			line_number: None,
+			command: None,
+			command_origin: None,
+			open_path: None,
+			picker: None,
+			settings: None,
+			status: None,
+			pending_key: None,
+			marks: HashMap::new(),
+			jumps: JumpList::default(),
+			color: git::color_enabled(repo),
+			columns: git::Columns {
+				hash_width: if config.full_hash {
+					FULL_HASH_WIDTH
+				} else {
+					config.abbrev
+				},
+				author_width: config.author_width.unwrap_or(0),
+				time_width: config.time_width,
+				summary_width: config.summary_width,
+				show_hash: shown(Column::Hash, true),
+				show_author: shown(Column::Author, true),
+				show_time: shown(Column::Time, true),
+				show_summary: shown(Column::Summary, false),
+				show_line_num: shown(Column::LineNumber, true),
+				order: Column::order(config.columns.as_deref().unwrap_or(&git::DEFAULT_COLUMNS)),
+				highlight_trailing_whitespace: false,
+				mark_blank_lines: config.mark_blank_lines,
+				no_color: git::no_color(),
+				mark_rewritten: false,
+				mark_merges: config.mark_merges,
+				author_initials: config.author_initials,
+				right_align_author: config.right_align_author,
+				human_dates: config.human_dates,
+				human_threshold: Duration::from_secs(config.human_date_days.saturating_mul(24 * 60 * 60)),
+				time_zone: config.time_zone,
+				recent_threshold: config
+					.recent_days
+					.map(|days| Duration::from_secs(days.saturating_mul(24 * 60 * 60))),
+			},
+			auto_author_width: config.author_width.is_none(),
+			scroll_amount: config.scroll,
+			search_wrap: config.search_wrap,
+			scrollbar: config.scrollbar,
+			split: 50,
+			mouse: config.mouse,
+			alt_screen: config.alt_screen,
+			divider: None,
+			dragging_divider: false,
+			refresh: config.refresh.map(Duration::from_secs),
+			panel_width: 80,
+			selection: config.selection,
+			link_remote: config
+				.hyperlinks
+				.then(|| git::remote(repo))
+				.flatten()
+				.map(|remote| remote.with_templates(config.url_templates.clone())),
+			url_templates: config.url_templates.clone(),
+			links: vec![],
+			ignore_whitespace: blame_options.ignore_whitespace,
+			blame_options,
+			followed_from: None,
+			remembered_rev: None,
+			tabs: vec![],
+			active_tab: 0,
+		}
+	}
+
+	// blames `rel_path` in a new tab and switches to it
+	pub fn open_tab(
+		&mut self,
+		rel_path: &Path,
+		commit: Oid,
+		followed_from: Option<PathBuf>,
+	) -> Result<(), Box<dyn Error>> {
+		let blame = self.blame_at(rel_path, commit)?;
+		if !self.tabs.is_empty() {
+			self.tabs[self.active_tab] = self.take_tab();
+		}
+		self.tabs.push(Tab::default());
+		self.active_tab = self.tabs.len() - 1;
+		self.load_tab(Tab {
+			commit_stack: vec![CommitPath {
+				commit,
+				path: rel_path.to_owned(),
+				blame_state: ListState::default(),
+				from_shown: false,
+			}],
+			followed_from,
+			..Default::default()
+		});
+		self.set_blame(blame);
+		Ok(())
+	}
+
+	pub fn switch_tab(&mut self, index: usize) {
+		if index == self.active_tab || index >= self.tabs.len() {
+			return;
+		}
+		self.tabs[self.active_tab] = self.take_tab();
+		let tab = mem::take(&mut self.tabs[index]);
+		self.active_tab = index;
+		self.load_tab(tab);
+		self.fit_author_width();
+	}
+
+	fn take_tab(&mut self) -> Tab {
+		Tab {
+			blame: mem::take(&mut self.blame),
+			blame_state: mem::take(&mut self.blame_state),
+			commit_stack: mem::take(&mut self.commit_stack),
+			file_view: self.file_view.take(),
+			marks: mem::take(&mut self.marks),
+			jumps: mem::take(&mut self.jumps),
+			followed_from: self.followed_from.take(),
+			remembered_rev: self.remembered_rev.take(),
+		}
+	}
+
+	fn load_tab(&mut self, tab: Tab) {
+		self.blame = tab.blame;
+		self.blame_state = tab.blame_state;
+		self.commit_stack = tab.commit_stack;
+		self.file_view = tab.file_view;
+		self.formatted = None;
+		self.marks = tab.marks;
+		self.jumps = tab.jumps;
+		self.followed_from = tab.followed_from;
+		self.remembered_rev = tab.remembered_rev;
+		// the panel belongs to a line of the previous file
+		self.set_panel(None);
+		self.shown_commit = None;
+		self.line_history_scroll = 0;
+		self.panel_x_scroll = 0;
+	}
+
+	// selects the line numbered `line`, counting from 1, or the last line if there aren't that many
+	pub fn go_to_line(&mut self, line: usize) {
+		self.select_line(line.max(1) - 1);
+	}
+
+	// offers `gr` to go back to where the current tab's file was blamed last time
+	pub fn offer_remembered_rev(&mut self, commit: Oid) {
+		self.remembered_rev = Some(commit);
+	}
+
+	// the file each tab ended up at and its revision, to remember for next time. tabs back at HEAD have None, so
+	// whatever was remembered for them is forgotten
+	pub fn tab_revisions(&self) -> Vec<(PathBuf, Option<Oid>)> {
+		let head = self.repo.head().ok().and_then(|head| head.target());
+		(0..self.tabs.len())
+			.map(|i| {
+				let stack = if i == self.active_tab {
+					&self.commit_stack
+				} else {
+					&self.tabs[i].commit_stack
+				};
+				let current = stack.last().unwrap();
+				let commit = (Some(current.commit) != head).then_some(current.commit);
+				(current.path.clone(), commit)
+			})
+			.collect()
+	}
+
+	// shows the blame of the current file at `commit` in the right panel, next to the blame being browsed
+	pub fn compare_with(&mut self, commit: Oid) -> Result<(), Box<dyn Error>> {
+		let path = self.commit_stack.last().unwrap().path.clone();
+		let blame = self.blame_at(&path, commit)?;
+		let now = SystemTime::now();
+		let mut lines = vec![Line::from(Span::styled(
+			format!("{} at {:.*}", path.display(), self.abbrev, commit.to_string()),
+			Style::default().fg(Color::Yellow),
+		))];
+		lines.extend(
+			blame
+				.iter()
+				.map(|hunk| git::format_blame_line(hunk, &self.columns, now)),
+		);
+		self.set_panel(Some(Text::from(lines)));
+		self.line_history_scroll = 0;
+		self.panel_x_scroll = 0;
+		Ok(())
+	}
+
+	// shows `commit` in the right panel, diffed against `parent` (1-based; 0 is the combined diff of a merge)
+	fn show_commit(&mut self, commit: Oid, parent: usize) {
+		// commits don't change, so a view only goes stale when it would be rendered differently
+		let key = (commit, parent, self.ignore_whitespace, self.panel_width);
+		if !self.shown_cache.contains_key(&key) {
+			if self.shown_cache.len() >= SHOWN_CACHE_SIZE {
+				self.shown_cache.clear();
+			}
+			let view = git::show(
+				self.repo,
+				commit,
+				parent,
+				self.color,
+				self.ignore_whitespace,
+				usize::from(self.panel_width),
+				self.abbrev,
+			);
+			self.shown_cache.insert(key, view);
+		}
+		let view = self.shown_cache[&key].clone();
+		self.set_panel(Some(view.text));
+		self.line_history_scroll = 0;
+		self.panel_x_scroll = 0;
+		self.shown_commit = Some(ShownCommit {
+			commit,
+			parent,
+			locations: view.locations,
+		});
+	}
+
+	// git's warnings go on the status line, so a mistyped whence.ignoreRevsFile doesn't go unnoticed
+	fn blame_at(&mut self, rel_path: &Path, commit: Oid) -> Result<Vec<git::BlameHunk>, Box<dyn Error>> {
+		let (blame, warning) = git::blame_with_warnings(self.repo, rel_path, commit, &self.blame_options)?;
+		if warning.is_some() {
+			self.status = warning;
+		}
+		Ok(blame)
+	}
+
+	// blames every tab again after `blame_options` changed, keeping the selected line
+	fn reblame(&mut self) {
+		for tab in &mut self.tabs {
+			// the active tab's slot is empty; its blame is on `self`
+			if let Some(commit_path) = tab.commit_stack.last() {
+				if let Ok(blame) = git::blame(self.repo, &commit_path.path, commit_path.commit, &self.blame_options) {
+					tab.blame = blame;
+				}
+			}
+		}
+		let commit_path = self.commit_stack.last().unwrap();
+		let (path, commit) = (commit_path.path.clone(), commit_path.commit);
+		match self.blame_at(&path, commit) {
+			Ok(blame) => {
+				let selected = self.blame_state.selected();
+				self.set_blame(blame);
+				if let Some(index) = selected {
+					self.select_line(index);
+				}
+			}
+			Err(e) => self.status = Some(format!("couldn't blame again: {}", e.to_string().trim_end())),
+		}
+	}
+
+	fn reload_file_view(&mut self) -> Result<(), git2::Error> {
+		if self.file_view.is_some() {
+			let commit_path = self.commit_stack.last().unwrap();
+			self.file_view = Some(git::file_lines(self.repo, &commit_path.path, commit_path.commit)?);
+			self.formatted = None;
+		}
+		Ok(())
+	}
+
+	// blames `path` at `commit` on top of the stack so `B` comes back
+	fn push_blame(&mut self, commit: Oid, path: PathBuf, from_shown: bool) -> Result<(), Box<dyn Error>> {
+		let blame = self.blame_at(&path, commit)?;
+		self.set_blame(blame);
+		self.commit_stack.last_mut().unwrap().blame_state = self.blame_state.clone();
+		self.commit_stack.push(CommitPath {
+			commit,
+			path,
+			blame_state: ListState::default(),
+			from_shown,
+		});
+		self.reload_file_view()?;
+		self.reapply_search();
+		Ok(())
+	}
+
+	// selects line `index`, or the last line when there aren't that many, or nothing in an empty file
+	fn select_line(&mut self, index: usize) {
+		let last = self.blame.len().checked_sub(1);
+		self.blame_state.select(last.map(|last| index.min(last)));
+	}
+
+	fn place(&self) -> Place {
+		Place {
+			commit_stack: self.commit_stack.clone(),
+			selected: self.blame_state.selected(),
+		}
+	}
+
+	// goes back (or forward) to a place from the jump list, reblaming if it was in another revision
+	fn go_to_place(&mut self, place: Place) -> Result<(), Box<dyn Error>> {
+		let (Some(top), Some(current)) = (place.commit_stack.last(), self.commit_stack.last()) else {
+			return Ok(());
+		};
+		if top.commit != current.commit || top.path != current.path {
+			let (path, commit) = (top.path.clone(), top.commit);
+			let blame = self.blame_at(&path, commit)?;
+			self.set_blame(blame);
+			self.commit_stack = place.commit_stack;
+			self.reload_file_view()?;
+			self.reapply_search();
+		} else {
+			self.commit_stack = place.commit_stack;
+		}
+		if let Some(index) = place.selected {
+			self.select_line(index);
+		}
+		Ok(())
+	}
+
+	// formats the blame, or the file view, unless the lines from the last draw would come out the same
+	fn format_lines(&mut self, now: SystemTime) {
+		if let Some(formatted) = &self.formatted {
+			let fresh = now
+				.duration_since(formatted.at)
+				.is_ok_and(|age| age < FORMATTED_MAX_AGE);
+			if fresh && formatted.columns == self.columns {
+				return;
+			}
+		}
+		let lines: Vec<Line<'static>> = match &self.file_view {
+			Some(file_lines) => file_lines
+				.iter()
+				.enumerate()
+				.map(|(i, code)| git::format_file_line(i + 1, code, &self.columns))
+				.collect(),
+			None => self
+				.blame
+				.iter()
+				.map(|hunk| git::format_blame_line(hunk, &self.columns, now))
+				.collect(),
+		};
+		self.formatted = Some(FormattedLines {
+			columns: self.columns.clone(),
+			at: now,
+			widths: lines.iter().map(Line::width).collect(),
+			lines,
+		});
+	}
+
+	fn set_blame(&mut self, blame: Vec<git::BlameHunk>) {
+		self.blame = blame;
+		self.formatted = None;
+		// line numbers mean something else in another revision
+		self.marks.clear();
+		self.fit_author_width();
+	}
+
+	fn set_panel(&mut self, panel: Option<Text<'static>>) {
+		self.right_panel = panel;
+		self.wrapped_panel = None;
+	}
+
+	// rewraps the shown commit's message when the terminal or the split changed the panel's width. this runs before
+	// drawing rather than in `ui` so drawing never has to run git
+	fn fit_panel(&mut self, term_size: Rect) {
+		let width = panel_text_width(self.split, term_size);
+		if width == self.panel_width || width == 0 {
+			return;
+		}
+		self.panel_width = width;
+		if let Some(shown) = &self.shown_commit {
+			let scroll = self.line_history_scroll;
+			self.show_commit(shown.commit, shown.parent);
+			self.line_history_scroll = scroll;
+		}
+	}
+
+	fn fit_author_width(&mut self) {
+		if self.auto_author_width {
+			self.columns.author_width = self
+				.blame
+				.iter()
+				.map(|hunk| layout::width(&hunk.meta.author))
+				.max()
+				.unwrap_or(0);
+		}
+	}
+
+	// after a reblame, moves to the nearest match of the active search in the new content and reports how many there are
+	fn reapply_search(&mut self) {
+		let Some(search) = self.search.as_ref().filter(|search| !search.editing) else {
+			return;
+		};
+		let matches = self
+			.blame
+			.iter()
+			.filter(|hunk| hunk.code.contains(&search.query))
+			.count();
+		if matches == 0 {
+			self.status = Some(format!("pattern not found in this revision: {}", search.query));
+			return;
+		}
+		let on_match = self
+			.blame_state
+			.selected()
+			.is_some_and(|index| self.blame[index].code.contains(&search.query));
+		if !on_match {
+			handle_search(&self.blame, &search.query, &mut self.blame_state, true, true);
 		}
+		self.status = Some(format!(
+			"{} match{} for {}",
+			matches,
+			if matches == 1 { "" } else { "es" },
+			search.query
+		));
 	}
 }
 
 type CrosstermTerm = Terminal<CrosstermBackend<Stdout>>;
 
-pub fn setup() -> Result<CrosstermTerm, Box<dyn Error>> {
+pub fn setup(mouse: bool, alt_screen: bool) -> Result<CrosstermTerm, Box<dyn Error>> {
+	install_panic_hook(alt_screen);
+	let backend = CrosstermBackend::new(io::stdout());
+	let mut terminal = Terminal::new(backend)?;
+	enter(&mut terminal, mouse, alt_screen)?;
+	Ok(terminal)
+}
+
+// capturing the mouse stops the terminal from selecting text, so it's only done when asked for
+fn enter(terminal: &mut CrosstermTerm, mouse: bool, alt_screen: bool) -> Result<(), Box<dyn Error>> {
 	enable_raw_mode()?;
-	let mut stdout = io::stdout();
-	execute!(stdout, EnterAlternateScreen)?;
-	let backend = CrosstermBackend::new(stdout);
-	Ok(Terminal::new(backend)?)
+	if alt_screen {
+		execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+	} else {
+		// only changed cells are drawn, so whatever was on the screen has to go first
+		terminal.clear()?;
+	}
+	if mouse {
+		execute!(terminal.backend_mut(), EnableMouseCapture)?;
+	}
+	Ok(())
 }
 
-pub fn teardown(terminal: &mut CrosstermTerm) {
+pub fn teardown(terminal: &mut CrosstermTerm, alt_screen: bool) {
 	_ = disable_raw_mode();
-	_ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
+	_ = execute!(terminal.backend_mut(), DisableMouseCapture);
+	if alt_screen {
+		_ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
+	} else if let Ok(size) = terminal.size() {
+		// leave the last frame where it is and put the shell's prompt under it
+		_ = terminal.set_cursor(0, size.height.saturating_sub(1));
+		_ = writeln!(terminal.backend_mut());
+	}
 	_ = terminal.show_cursor();
 }
 
-pub fn run_app(terminal: &mut CrosstermTerm, mut app: App) -> Result<(), Box<dyn Error>> {
+thread_local! {
+	static CATCHING_PANIC: Cell<bool> = const { Cell::new(false) };
+	static PANIC_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// panics caught by `catch_panic` are recorded for a popup; any other panic restores the terminal before reporting
+fn install_panic_hook(alt_screen: bool) {
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		if CATCHING_PANIC.get() {
+			PANIC_MESSAGE.set(Some(info.to_string()));
+		} else {
+			_ = disable_raw_mode();
+			_ = execute!(io::stdout(), DisableMouseCapture);
+			if alt_screen {
+				_ = execute!(io::stdout(), LeaveAlternateScreen);
+			}
+			default_hook(info);
+		}
+	}));
+}
+
+// a bug handling one key shouldn't take down the whole session
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+	CATCHING_PANIC.set(true);
+	let result = panic::catch_unwind(AssertUnwindSafe(f));
+	CATCHING_PANIC.set(false);
+	result.map_err(|_| PANIC_MESSAGE.take().unwrap_or_else(|| "unknown panic".to_owned()))
+}
+
+// raw mode turns ctrl-z into a plain key press, so stop ourselves the way the shell would have
+#[cfg(unix)]
+fn suspend(terminal: &mut CrosstermTerm, mouse: bool, alt_screen: bool) -> Result<(), Box<dyn Error>> {
+	teardown(terminal, alt_screen);
+	// SAFETY: raise only delivers a signal to this process; execution resumes here after SIGCONT
+	unsafe {
+		libc::raise(libc::SIGTSTP);
+	}
+	enter(terminal, mouse, alt_screen)?;
+	terminal.clear()?;
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn suspend(_terminal: &mut CrosstermTerm, _mouse: bool, _alt_screen: bool) -> Result<(), Box<dyn Error>> {
+	Ok(())
+}
+
+pub fn run_app(terminal: &mut CrosstermTerm, app: &mut App) -> Result<(), Box<dyn Error>> {
 	loop {
-		terminal.draw(|frame| ui(frame, &mut app))?;
-		if let Event::Key(key) = event::read()? {
-			match handle_input(&key, &mut app, &terminal.size()?) {
-				Ok(false) => {
-					return Ok(());
+		app.fit_panel(terminal.size()?);
+		terminal.draw(|frame| ui(frame, app))?;
+		draw_links(terminal, &app.links)?;
+		// blame lines are formatted on every draw, so redrawing is all it takes for "2 minutes ago" to age
+		if let Some(refresh) = app.refresh {
+			if !event::poll(refresh)? {
+				continue;
+			}
+		}
+		let key = match event::read()? {
+			Event::Key(key) => key,
+			Event::Mouse(mouse) => {
+				handle_mouse(&mouse, app, terminal.size()?.width);
+				continue;
+			}
+			_ => continue,
+		};
+		if key.modifiers == KeyModifiers::CONTROL {
+			match key.code {
+				Char('z') => {
+					suspend(terminal, app.mouse, app.alt_screen)?;
+					continue;
+				}
+				// unlike `q`, doesn't close panels, prompts and popups one at a time first
+				Char('q') => return Ok(()),
+				Char('l') => {
+					// forget what ratatui thinks is on screen so the next draw repaints everything
+					terminal.clear()?;
+					continue;
 				}
-				Ok(true) => {} // ignored
-				Err(err) => app.popup = Some(format!("{}", err).into()),
+				_ => {}
+			}
+		}
+		let term_size = terminal.size()?;
+		match catch_panic(|| handle_input(&key, app, &term_size)) {
+			Ok(Ok(false)) => {
+				return Ok(());
 			}
+			Ok(Ok(true)) => {} // ignored
+			Ok(Err(err)) => app.popup = Some(format!("{}", err).into()),
+			Err(panic_message) => app.popup = Some(format!("panic: {}", panic_message).into()),
 		}
 	}
 }
 
-// returns whether to continue running the app
+fn draw_links(terminal: &mut CrosstermTerm, links: &[Link]) -> io::Result<()> {
+	if links.is_empty() {
+		return Ok(());
+	}
+	for link in links {
+		let mut cells = link.cells.clone();
+		let last = cells.len() - 1;
+		let open = format!("\x1b]8;;{}\x1b\\{}", link.url, cells[0].symbol);
+		cells[0].set_symbol(&open);
+		let close = format!("{}\x1b]8;;\x1b\\", cells[last].symbol);
+		cells[last].set_symbol(&close);
+		let positions = (link.x..).zip(cells.iter()).map(|(x, cell)| (x, link.y, cell));
+		terminal.backend_mut().draw(positions)?;
+	}
+	Backend::flush(terminal.backend_mut())
+}
+
+// how close to the divider a press has to be to start dragging it
+const DIVIDER_GRAB: u16 = 1;
+const SPLIT_MIN: u16 = 20;
+const SPLIT_MAX: u16 = 80;
+
+fn handle_mouse(mouse: &MouseEvent, app: &mut App, width: u16) {
+	match mouse.kind {
+		MouseEventKind::Down(MouseButton::Left) => {
+			app.dragging_divider = app.divider.is_some_and(|x| mouse.column.abs_diff(x) <= DIVIDER_GRAB);
+		}
+		MouseEventKind::Drag(MouseButton::Left) if app.dragging_divider && width > 0 => {
+			let percent = u32::from(mouse.column) * 100 / u32::from(width);
+			app.split = (percent as u16).clamp(SPLIT_MIN, SPLIT_MAX);
+		}
+		MouseEventKind::Up(MouseButton::Left) => app.dragging_divider = false,
+		_ => {} // ignored
+	}
+}
+
+// remembers where a jump started, for ctrl-o
 fn handle_input(key: &KeyEvent, app: &mut App, term_size: &Rect) -> Result<bool, Box<dyn Error>> {
+	if !is_jump(app, key) {
+		return handle_key(key, app, term_size);
+	}
+	let mut before = app.place();
+	if app.command.is_some() {
+		// the selection is only previewing the line being typed
+		before.selected = app.command_origin;
+	}
+	let result = handle_key(key, app, term_size);
+	if !app.place().same_as(&before) {
+		app.jumps.record(before);
+	}
+	result
+}
+
+// keys that can move far enough that ctrl-o should be able to come back. the jump is only recorded if they did move
+fn is_jump(app: &App, key: &KeyEvent) -> bool {
+	if app.popup.is_some() || app.settings.is_some() || app.picker.is_some() || app.open_path.is_some() {
+		return false;
+	}
+	if let Some(pending) = app.pending_key {
+		return matches!(pending, '\'' | ']' | '[' | 'g');
+	}
+	if app.command.is_some() || app.search.as_ref().is_some_and(|search| search.editing) {
+		return key.code == KeyCode::Enter;
+	}
+	!key.modifiers.contains(KeyModifiers::CONTROL)
+		&& matches!(
+			key.code,
+			Char('G' | 'n' | 'N' | 'b' | 'B' | 'L' | 'R') | KeyCode::Home | KeyCode::End
+		)
+}
+
+// returns whether to continue running the app
+fn handle_key(key: &KeyEvent, app: &mut App, term_size: &Rect) -> Result<bool, Box<dyn Error>> {
+	app.status = None;
+	if let (Some(popup), Some(help)) = (&app.popup, &mut app.help) {
+		let max = panel_height(popup).saturating_sub(1);
+		match key.code {
+			Char('j') | KeyCode::Down => help.scroll = (help.scroll + 1).min(max),
+			Char('k') | KeyCode::Up => help.scroll = help.scroll.saturating_sub(1),
+			Char('d') | Char(' ') | KeyCode::PageDown => {
+				help.scroll = help.scroll.saturating_add(term_size.height / 2).min(max)
+			}
+			Char('u') | KeyCode::PageUp => help.scroll = help.scroll.saturating_sub(term_size.height / 2),
+			Char('f') => help.full_screen = !help.full_screen,
+			_ => {
+				app.popup = None;
+				app.help = None;
+			}
+		}
+		return Ok(true);
+	}
 	if app.popup.is_some() {
 		// clear the popup on any key press
 		app.popup = None;
 		return Ok(true);
 	}
 
+	if let Some(pending) = app.pending_key.take() {
+		if let KeyEvent { code: Char(c), .. } = key {
+			handle_pending_key(app, pending, *c);
+		}
+		return Ok(true);
+	}
+
 	if let Some(search) = &mut app.search {
 		if search.editing {
 			match key {
@@ -135,87 +1120,259 @@ fn handle_input(key: &KeyEvent, app: &mut App, term_size: &Rect) -> Result<bool,
 					code: KeyCode::Enter, ..
 				} => {
 					search.editing = false;
-					handle_search(&app.blame, &search.query, &mut app.blame_state, true);
+					app.status = handle_search(&app.blame, &search.query, &mut app.blame_state, true, app.search_wrap);
 				}
 				_ => {} // ignored
 			}
 			return Ok(true);
 		}
-	} else if let Some(line_number) = &mut app.line_number {
+	} else if let Some(command) = &mut app.command {
 		match key {
 			KeyEvent { code: KeyCode::Esc, .. }
 			| KeyEvent {
 				code: Char('c'),
 				modifiers: KeyModifiers::CONTROL,
 				..
-			} => app.line_number = None,
+			} => {
+				app.command = None;
+				if let Some(origin) = app.command_origin.take() {
+					app.select_line(origin);
+				}
+			}
 			KeyEvent {
 				code: Char('u'),
 				modifiers: KeyModifiers::CONTROL,
 				..
 			} => {
-				line_number.clear();
+				command.clear();
+				preview_line(app);
 			}
-			KeyEvent { code: Char(c), .. } => {
-				if '0' <= *c && *c <= '9' {
-					line_number.push(*c);
-				}
+			KeyEvent {
+				code: Char(c),
+				modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+				..
+			} => {
+				command.push(*c);
+				preview_line(app);
 			}
 			KeyEvent {
 				code: KeyCode::Backspace,
 				..
 			} => {
-				line_number.pop();
+				command.pop();
+				preview_line(app);
 			}
 			KeyEvent {
 				code: KeyCode::Enter, ..
 			} => {
-				if let Ok(index) = line_number.parse::<usize>() {
-					app.blame_state.select(Some(index.clamp(1, app.blame.len()) - 1));
-					app.line_number = None;
-				}
+				let command = app.command.take().unwrap();
+				app.command_origin = None;
+				app.status = run_command(app, command.trim());
 			}
 			_ => {} // ignored
 		}
 		return Ok(true);
-	}
-
-	match key {
-		// scroll
-		KeyEvent {
-			code: Char('j') | KeyCode::Down,
-			..
-		} => scroll(app, term_size, 1),
-		KeyEvent {
-			code: Char('k') | KeyCode::Up,
-			..
-		} => scroll(app, term_size, -1),
-		KeyEvent { code: Char('d'), .. }
-		| KeyEvent {
-			code: KeyCode::PageDown,
-			..
-		} => scroll(app, term_size, (term_size.height / 2).try_into().unwrap()),
-		KeyEvent { code: Char('u'), .. }
-		| KeyEvent {
-			code: KeyCode::PageUp, ..
-		} => scroll(app, term_size, -i16::try_from(term_size.height / 2).unwrap()),
-		KeyEvent { code: Char('g'), .. }
-		| KeyEvent {
-			code: KeyCode::Home, ..
-		} => match &app.right_panel {
-			Some(_) => app.line_history_scroll = 0,
-			None => app.blame_state.select(Some(0)),
-		},
-		KeyEvent { code: Char('G'), .. } | KeyEvent { code: KeyCode::End, .. } => match &app.right_panel {
-			Some(line_history) => {
-				app.line_history_scroll = u16::try_from(line_history.height())
-					.unwrap()
-					.saturating_sub(term_size.height)
+	} else if let Some(settings) = &mut app.settings {
+		let selected = settings.selected().unwrap_or(0);
+		match key {
+			KeyEvent {
+				code: KeyCode::Esc | Char('q' | 's'),
+				..
+			} => app.settings = None,
+			KeyEvent {
+				code: KeyCode::Up | Char('k'),
+				..
+			} => settings.select(Some(selected.saturating_sub(1))),
+			KeyEvent {
+				code: KeyCode::Down | Char('j'),
+				..
+			} => settings.select(Some((selected + 1).min(SETTINGS.len() - 1))),
+			KeyEvent {
+				code: KeyCode::Enter | Char(' '),
+				..
+			} => {
+				let setting = &SETTINGS[selected];
+				(setting.set)(app, !(setting.get)(app));
+			}
+			KeyEvent { code: Char('w'), .. } => {
+				save_settings(app)?;
+				app.status = Some("saved to the global git config".to_owned());
+			}
+			_ => {} // ignored
+		}
+		return Ok(true);
+	} else if let Some(picker) = &mut app.picker {
+		match key {
+			KeyEvent { code: KeyCode::Esc, .. }
+			| KeyEvent {
+				code: Char('c'),
+				modifiers: KeyModifiers::CONTROL,
+				..
+			} => app.picker = None,
+			KeyEvent { code: KeyCode::Up, .. }
+			| KeyEvent {
+				code: Char('p'),
+				modifiers: KeyModifiers::CONTROL,
+				..
+			} => picker.select(-1),
+			KeyEvent {
+				code: KeyCode::Down, ..
+			}
+			| KeyEvent {
+				code: Char('n'),
+				modifiers: KeyModifiers::CONTROL,
+				..
+			} => picker.select(1),
+			KeyEvent {
+				code: Char('u'),
+				modifiers: KeyModifiers::CONTROL,
+				..
+			} => {
+				picker.query.clear();
+				picker.refilter();
+			}
+			KeyEvent { code: Char(c), .. } => {
+				picker.query.push(*c);
+				picker.refilter();
+			}
+			KeyEvent {
+				code: KeyCode::Backspace,
+				..
+			} => {
+				picker.query.pop();
+				picker.refilter();
+			}
+			KeyEvent {
+				code: KeyCode::Enter, ..
+			} => {
+				if let Some(index) = picker.state.selected() {
+					let path = PathBuf::from(&picker.files[picker.matches[index]]);
+					app.picker = None;
+					let commit = app.commit_stack[0].commit;
+					app.open_tab(&path, commit, None)?;
+				}
+			}
+			_ => {} // ignored
+		}
+		return Ok(true);
+	} else if let Some(open_path) = &mut app.open_path {
+		match key {
+			KeyEvent { code: KeyCode::Esc, .. }
+			| KeyEvent {
+				code: Char('c'),
+				modifiers: KeyModifiers::CONTROL,
+				..
+			} => app.open_path = None,
+			KeyEvent {
+				code: Char('u'),
+				modifiers: KeyModifiers::CONTROL,
+				..
+			} => {
+				open_path.clear();
+			}
+			KeyEvent { code: Char(c), .. } => {
+				open_path.push(*c);
 			}
-			None => app.blame_state.select(Some(app.blame.len() - 1)),
+			KeyEvent {
+				code: KeyCode::Backspace,
+				..
+			} => {
+				open_path.pop();
+			}
+			KeyEvent {
+				code: KeyCode::Enter, ..
+			} => {
+				let path = PathBuf::from(app.open_path.take().unwrap().trim());
+				// at the revision this tab started from, not wherever `b` has since gone
+				let commit = app.commit_stack[0].commit;
+				if git::path_exists(app.repo, &path, commit) {
+					app.open_tab(&path, commit, None)?;
+				} else {
+					let short_commit = &commit.to_string()[..app.abbrev];
+					app.popup = Some(format!("no such file '{}' at {}", path.display(), short_commit).into());
+				}
+			}
+			_ => {} // ignored
+		}
+		return Ok(true);
+	}
+
+	match key {
+		// scroll
+		KeyEvent {
+			code: Char('j') | KeyCode::Down,
+			..
+		} => scroll(app, term_size, 1),
+		KeyEvent {
+			code: Char('k') | KeyCode::Up,
+			..
+		} => scroll(app, term_size, -1),
+		// before the plain letters, which would otherwise match with any modifiers
+		KeyEvent {
+			code: Char('f'),
+			modifiers: KeyModifiers::CONTROL,
+			..
+		}
+		| KeyEvent { code: Char(' '), .. } => scroll(app, term_size, full_page(app, term_size)),
+		KeyEvent {
+			code: Char('b'),
+			modifiers: KeyModifiers::CONTROL,
+			..
+		} => scroll(app, term_size, -full_page(app, term_size)),
+		KeyEvent { code: Char('d'), .. }
+		| KeyEvent {
+			code: KeyCode::PageDown,
+			..
+		} => scroll(app, term_size, half_page(app, term_size)),
+		KeyEvent { code: Char('u'), .. }
+		| KeyEvent {
+			code: KeyCode::PageUp, ..
+		} => scroll(app, term_size, -half_page(app, term_size)),
+		KeyEvent {
+			code: KeyCode::Home, ..
+		} => jump_to_top(app),
+		KeyEvent { code: Char('G'), .. } | KeyEvent { code: KeyCode::End, .. } => match &app.right_panel {
+			Some(line_history) => app.line_history_scroll = last_panel_scroll(app, line_history, term_size.height),
+			None => app.select_line(app.blame.len().saturating_sub(1)),
 		},
+		KeyEvent {
+			code: Char(c @ ('m' | '\'' | ']' | '[' | 'y' | 'g')),
+			..
+		} => app.pending_key = Some(*c),
 		KeyEvent { code: Char(':'), .. } => {
-			app.line_number = Some(String::new());
+			app.command = Some(String::new());
+			app.command_origin = app.blame_state.selected();
+		}
+		KeyEvent {
+			code: Char('o'),
+			modifiers: KeyModifiers::CONTROL,
+			..
+		} => {
+			let here = app.place();
+			match app.jumps.back(here) {
+				Some(place) => app.go_to_place(place)?,
+				None => app.status = Some("at the start of the jump list".to_owned()),
+			}
+		}
+		// not vim's ctrl-i, which terminals send as tab and so switches tabs
+		KeyEvent {
+			code: Char('n'),
+			modifiers: KeyModifiers::CONTROL,
+			..
+		} => match app.jumps.forward() {
+			Some(place) => app.go_to_place(place)?,
+			None => app.status = Some("at the end of the jump list".to_owned()),
+		},
+		KeyEvent { code: Char('o'), .. } => {
+			app.open_path = Some(String::new());
+		}
+		KeyEvent {
+			code: Char('p'),
+			modifiers: KeyModifiers::CONTROL,
+			..
+		} => {
+			// files as of the revision this tab started from, like `o`
+			app.picker = Some(Picker::new(git::tracked_files(app.repo, app.commit_stack[0].commit)?));
 		}
 		// search
 		KeyEvent { code: Char('/'), .. } => {
@@ -226,62 +1383,223 @@ fn handle_input(key: &KeyEvent, app: &mut App, term_size: &Rect) -> Result<bool,
 		}
 		KeyEvent { code: Char('n'), .. } => {
 			if let Some(search) = &app.search {
-				handle_search(&app.blame, &search.query, &mut app.blame_state, true);
+				app.status = handle_search(&app.blame, &search.query, &mut app.blame_state, true, app.search_wrap);
 			}
 		}
 		KeyEvent { code: Char('N'), .. } => {
 			if let Some(search) = &app.search {
-				handle_search(&app.blame, &search.query, &mut app.blame_state, false);
+				app.status = handle_search(&app.blame, &search.query, &mut app.blame_state, false, app.search_wrap);
 			}
 		}
 		// other interactions
 		KeyEvent {
 			code: KeyCode::Enter, ..
+		} => match app.blame_state.selected().map(|index| app.blame[index].commit) {
+			Some(commit) if commit.is_zero() => app.status = Some("not committed yet, only staged".to_owned()),
+			Some(commit) => app.show_commit(commit, 1),
+			None => {}
+		},
+		KeyEvent {
+			code: Char(c @ '0'..='9'),
+			..
+		} if picks_merge_parent(app, *c) => {
+			let shown = app.shown_commit.as_ref().unwrap();
+			let parent = c.to_digit(10).unwrap() as usize;
+			if parent != shown.parent {
+				app.show_commit(shown.commit, parent);
+			}
+		}
+		KeyEvent {
+			code: Char(c @ '1'..='9'),
+			..
+		} => app.switch_tab(c.to_digit(10).unwrap() as usize - 1),
+		KeyEvent { code: KeyCode::Tab, .. } => app.switch_tab((app.active_tab + 1) % app.tabs.len()),
+		KeyEvent {
+			code: KeyCode::BackTab, ..
+		} => app.switch_tab((app.active_tab + app.tabs.len() - 1) % app.tabs.len()),
+		KeyEvent {
+			code: Char('w'),
+			modifiers: KeyModifiers::CONTROL,
+			..
 		} => {
+			app.ignore_whitespace = !app.ignore_whitespace;
+			if let Some(shown) = &app.shown_commit {
+				app.show_commit(shown.commit, shown.parent);
+			}
+		}
+		KeyEvent { code: Char('L'), .. } if app.shown_commit.is_some() => {
+			app.status = jump_to_diff_line(app)?;
+		}
+		KeyEvent { code: Char('R'), .. } if app.shown_commit.is_some() => {
+			app.status = blame_at_shown_commit(app)?;
+		}
+		KeyEvent { code: Char('a'), .. } => {
+			if let Some(index) = app.blame_state.selected() {
+				app.status = Some(author_identity(&app.blame[index].meta));
+			}
+		}
+		KeyEvent { code: Char('E'), .. } => {
+			app.status = Some(match &app.shown_commit {
+				Some(shown) => export_patch(app, shown.commit, shown.parent)?,
+				None => "show a commit with enter first".to_owned(),
+			});
+		}
+		KeyEvent { code: Char('f'), .. } => {
 			if let Some(index) = app.blame_state.selected() {
-				app.right_panel = Some(git::show(app.repo, app.blame[index].commit));
+				let hunk = &app.blame[index];
+				let path = match &hunk.path {
+					Some(p) => p,
+					None => &app.commit_stack.last().unwrap().path,
+				};
+				let orig_line = usize::try_from(hunk.orig_line_num).unwrap_or(1);
+				app.set_panel(Some(git::file_at_commit(
+					app.repo,
+					path,
+					hunk.commit,
+					orig_line,
+					app.selection,
+					app.abbrev,
+				)));
+				app.shown_commit = None;
+				// the header takes the first row, so this leaves the line a few rows below the top
+				app.line_history_scroll = u16::try_from(orig_line.saturating_sub(5)).unwrap_or(u16::MAX);
+				app.panel_x_scroll = 0;
 			}
 		}
 		KeyEvent { code: Char('w'), .. } => {
 			if let Some(index) = app.blame_state.selected() {
+				app.shown_commit = None;
 				let commit_path = app.commit_stack.last().unwrap();
-				app.right_panel = Some(git::log_follow(app.repo, &commit_path.path, index, commit_path.commit));
+				app.set_panel(Some(git::log_follow(
+					app.repo,
+					&commit_path.path,
+					index,
+					commit_path.commit,
+					app.color,
+				)));
+				app.panel_x_scroll = 0;
 			}
 		}
 		KeyEvent { code: Char('b'), .. } => {
 			if let Some(index) = app.blame_state.selected() {
-				let blame = &app.blame[index];
-				let parent = app.repo.find_commit(blame.commit)?.parent_id(0)?;
-				let line_path = match blame.path.to_owned() {
-					Some(p) => p,
-					None => app.commit_stack.last().unwrap().path.to_owned(),
-				};
-				app.blame = git::blame(app.repo, &line_path, parent)?;
-				app.blame_state.select(Some(index.min(app.blame.len() - 1)));
+				let (parent, line_path) =
+					git::blame_parent(app.repo, &app.blame[index], &app.commit_stack.last().unwrap().path)?;
+				let blame = app.blame_at(&line_path, parent)?;
+				app.set_blame(blame);
+				app.commit_stack.last_mut().unwrap().blame_state = app.blame_state.clone();
+				app.select_line(index);
 				app.commit_stack.push(CommitPath {
 					commit: parent,
 					path: line_path,
+					blame_state: ListState::default(),
+					from_shown: false,
 				});
+				app.reload_file_view()?;
+				app.reapply_search();
 			}
 		}
-		KeyEvent { code: Char('B'), .. } => {
-			if app.commit_stack.len() > 1 {
-				app.commit_stack.pop();
-				let commit_path = app.commit_stack.last().unwrap();
-				app.blame = git::blame(app.repo, &commit_path.path, commit_path.commit)?;
-				if let Some(index) = app.blame_state.selected() {
-					app.blame_state.select(Some(index.min(app.blame.len() - 1)));
+		KeyEvent { code: Char('x'), .. } => {
+			if let Some(index) = app.blame_state.selected() {
+				let commit = app.blame[index].commit;
+				if commit.is_zero() {
+					app.status = Some("staged lines have no commit to ignore".to_owned());
+				} else if !app.blame_options.ignore_revs.contains(&commit) {
+					app.blame_options.ignore_revs.push(commit);
+					app.reblame();
+					app.status
+						.get_or_insert(format!("ignoring {:.*} (X to stop)", app.abbrev, commit.to_string()));
 				}
 			}
 		}
-		KeyEvent { code: Char('h'), .. } => app.popup = Some(make_help_text()),
+		KeyEvent { code: Char('X'), .. } => {
+			if let Some(commit) = app.blame_options.ignore_revs.pop() {
+				app.reblame();
+				app.status
+					.get_or_insert(format!("no longer ignoring {:.*}", app.abbrev, commit.to_string()));
+			}
+		}
+		KeyEvent { code: Char('B'), .. } if app.commit_stack.len() > 1 => {
+			app.commit_stack.pop();
+			let commit_path = app.commit_stack.last().unwrap();
+			let (path, commit, blame_state) = (
+				commit_path.path.clone(),
+				commit_path.commit,
+				commit_path.blame_state.clone(),
+			);
+			let blame = app.blame_at(&path, commit)?;
+			app.blame_state = blame_state;
+			app.set_blame(blame);
+			if let Some(index) = app.blame_state.selected() {
+				app.select_line(index);
+			}
+			app.reload_file_view()?;
+			app.reapply_search();
+		}
+		KeyEvent { code: Char('v'), .. } => {
+			if app.file_view.is_some() {
+				app.file_view = None;
+			} else {
+				let commit_path = app.commit_stack.last().unwrap();
+				app.file_view = Some(git::file_lines(app.repo, &commit_path.path, commit_path.commit)?);
+			}
+			app.formatted = None;
+		}
+		KeyEvent { code: Char('s'), .. } => {
+			let mut state = ListState::default();
+			state.select(Some(0));
+			app.settings = Some(state);
+		}
+		KeyEvent { code: Char('H'), .. } => {
+			app.columns.hash_width = if app.columns.hash_width == FULL_HASH_WIDTH {
+				app.abbrev
+			} else {
+				FULL_HASH_WIDTH
+			};
+		}
+		KeyEvent { code: Char('#'), .. } => app.columns.show_hash = !app.columns.show_hash,
+		KeyEvent { code: Char('A'), .. } => app.columns.show_author = !app.columns.show_author,
+		KeyEvent { code: Char('T'), .. } => app.columns.show_time = !app.columns.show_time,
+		KeyEvent { code: Char('S'), .. } => app.columns.show_summary = !app.columns.show_summary,
+		KeyEvent { code: Char('W'), .. } => {
+			app.columns.highlight_trailing_whitespace = !app.columns.highlight_trailing_whitespace
+		}
+		KeyEvent { code: Char('I'), .. } => app.columns.author_initials = !app.columns.author_initials,
+		KeyEvent { code: Char('D'), .. } => app.columns.mark_rewritten = !app.columns.mark_rewritten,
+		KeyEvent { code: Char('F'), .. } => app.footer = !app.footer,
+		KeyEvent { code: Char('Z'), .. } => {
+			app.columns.time_zone = app.columns.time_zone.next();
+			app.status = Some(format!("dates in {}", app.columns.time_zone.name()));
+		}
+		KeyEvent { code: Char('i'), .. } => {
+			if let Some(index) = app.blame_state.selected() {
+				app.popup = Some(make_line_info(&app.blame[index], app.columns.time_zone));
+			}
+		}
+		KeyEvent { code: Char('<'), .. } => app.split = app.split.saturating_sub(5).max(SPLIT_MIN),
+		KeyEvent { code: Char('>'), .. } => app.split = (app.split + 5).min(SPLIT_MAX),
+		// wrapped lines always fit, so there's nothing to scroll to
+		KeyEvent {
+			code: KeyCode::Left, ..
+		} if app.right_panel.is_some() && !app.panel_wrap => scroll_panel_sideways(app, false),
+		KeyEvent {
+			code: KeyCode::Right, ..
+		} if app.right_panel.is_some() && !app.panel_wrap => scroll_panel_sideways(app, true),
+		KeyEvent {
+			code: Char('h') | Char('?'),
+			..
+		} => {
+			app.popup = Some(make_help_text());
+			app.help = Some(HelpView::default());
+		}
 		KeyEvent {
 			code: Char('q') | KeyCode::Esc,
 			..
 		} => {
 			if app.right_panel.is_some() {
-				app.right_panel = None;
+				app.set_panel(None);
+				app.shown_commit = None;
 				app.line_history_scroll = 0;
+				app.panel_x_scroll = 0;
 			} else {
 				return Ok(false);
 			}
@@ -291,62 +1609,565 @@ fn handle_input(key: &KeyEvent, app: &mut App, term_size: &Rect) -> Result<bool,
 	Ok(true)
 }
 
+fn handle_pending_key(app: &mut App, pending: char, c: char) {
+	match pending {
+		'm' if c.is_ascii_alphabetic() => {
+			if let Some(index) = app.blame_state.selected() {
+				app.marks.insert(c, index);
+				app.status = Some(format!("mark '{}' set", c));
+			}
+		}
+		'\'' => match app.marks.get(&c) {
+			Some(index) => app.blame_state.select(Some(*index)),
+			None => app.status = Some(format!("mark '{}' not set", c)),
+		},
+		']' | '[' if c == 'h' => jump_to_same_commit(app, pending == ']'),
+		']' | '[' if c == 'A' => {
+			if let Some(index) = app.blame_state.selected() {
+				let author = app.blame[index].meta.author.clone();
+				app.status = jump_to_author(app, pending == ']', &author, |name| name == author);
+			}
+		}
+		'g' if c == 'g' => jump_to_top(app),
+		'g' if c == 'r' => {
+			if let Some(commit) = app.remembered_rev.take() {
+				let path = app.commit_stack[0].path.clone();
+				if let Err(e) = app.push_blame(commit, path, false) {
+					app.status = Some(format!(
+						"couldn't blame at {:.*}: {}",
+						app.abbrev,
+						commit.to_string(),
+						e
+					));
+				}
+			}
+		}
+		'y' => {
+			let copied = match c {
+				'l' => permalink(app),
+				'p' => file_path(app, false),
+				'P' => file_path(app, true),
+				'm' => commit_message(app),
+				_ => return,
+			};
+			app.status = Some(match copied {
+				Ok(text) => {
+					// the status line only has room for a commit message's summary
+					let first_line = text.lines().next().unwrap_or_default();
+					match clipboard::copy(&text) {
+						Ok(()) => format!("copied {}", first_line),
+						Err(e) => format!("couldn't copy {}: {}", first_line, e),
+					}
+				}
+				Err(e) => e,
+			});
+		}
+		_ => {} // ignored
+	}
+}
+
+// moves the panel half its width left or right, stopping once its widest line ends at the right edge
+fn scroll_panel_sideways(app: &mut App, right: bool) {
+	let step = (app.panel_width / 2).max(1);
+	if right {
+		let widest = app.right_panel.as_ref().map_or(0, Text::width);
+		let max = u16::try_from(widest.saturating_sub(usize::from(app.panel_width))).unwrap_or(u16::MAX);
+		app.panel_x_scroll = app.panel_x_scroll.saturating_add(step).min(max);
+	} else {
+		app.panel_x_scroll = app.panel_x_scroll.saturating_sub(step);
+	}
+}
+
+fn jump_to_top(app: &mut App) {
+	match &app.right_panel {
+		Some(_) => app.line_history_scroll = 0,
+		None => app.select_line(0),
+	}
+}
+
+// a forge link to the selected line, pinned to the commit being blamed
+fn permalink(app: &App) -> Result<String, String> {
+	let index = app.blame_state.selected().ok_or("no line selected")?;
+	let remote = git::remote(app.repo)
+		.ok_or("no remote with a recognizable url")?
+		.with_templates(app.url_templates.clone());
+	let commit_path = app.commit_stack.last().unwrap();
+	if commit_path.commit.is_zero() {
+		return Err("the staged version isn't on the forge".to_owned());
+	}
+	let line = usize::try_from(app.blame[index].line_num).unwrap_or(1);
+	// forges want forward slashes whatever the platform
+	let path = commit_path.path.to_string_lossy().replace('\\', "/");
+	Ok(remote.permalink(&commit_path.commit.to_string(), &path, line))
+}
+
+// the selected line's commit message, summary and body
+fn commit_message(app: &App) -> Result<String, String> {
+	let index = app.blame_state.selected().ok_or("no line selected")?;
+	let commit = app
+		.repo
+		.find_commit(app.blame[index].commit)
+		.map_err(|e| e.to_string())?;
+	Ok(String::from_utf8_lossy(commit.message_bytes()).trim_end().to_owned())
+}
+
+// the blamed file's path, relative to the repo or absolute, with `:line` for the selected line
+fn file_path(app: &App, absolute: bool) -> Result<String, String> {
+	let rel_path = &app.commit_stack.last().unwrap().path;
+	let path = if absolute {
+		let workdir = app.repo.workdir().ok_or("a bare repository has no working tree")?;
+		workdir.join(rel_path)
+	} else {
+		rel_path.clone()
+	};
+	let mut text = path.to_string_lossy().into_owned();
+	if let Some(index) = app.blame_state.selected() {
+		text += &format!(":{}", app.blame[index].line_num);
+	}
+	Ok(text)
+}
+
+// writes the shown commit to `<hash>.patch` in the current directory, leaving any existing file alone
+fn export_patch(app: &App, commit: Oid, parent: usize) -> Result<String, Box<dyn Error>> {
+	if parent == 0 {
+		return Ok("a combined diff can't be exported; pick a parent with its number first".to_owned());
+	}
+	let patch = git::patch(app.repo, commit, parent, app.ignore_whitespace)?;
+	let path = format!("{:.*}.patch", app.abbrev, commit.to_string());
+	let mut file = fs::OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(&path)
+		.map_err(|e| format!("couldn't write {}: {}", path, e))?;
+	file.write_all(&patch)?;
+	Ok(format!("wrote {}", path))
+}
+
+// selects, in the blame, the diff line at the top of the panel: an added or unchanged line as of the shown commit,
+// or a deleted line as of its parent, reblaming at that revision when it's not the one being browsed
+fn jump_to_diff_line(app: &mut App) -> Result<Option<String>, Box<dyn Error>> {
+	let shown = app.shown_commit.as_ref().unwrap();
+	if shown.parent == 0 {
+		return Ok(Some(
+			"L needs a diff against one parent; pick one with its number".to_owned(),
+		));
+	}
+	let Some(Some(location)) = shown.locations.get(usize::from(app.line_history_scroll)).cloned() else {
+		return Ok(Some(
+			"scroll a line of the diff to the top of the panel first".to_owned(),
+		));
+	};
+	let (commit, line) = match (location.new_line, location.old_line) {
+		(Some(new_line), _) => (shown.commit, new_line),
+		(None, Some(old_line)) => (
+			app.repo.find_commit(shown.commit)?.parent_id(shown.parent - 1)?,
+			old_line,
+		),
+		(None, None) => return Ok(None),
+	};
+	let current = app.commit_stack.last().unwrap();
+	if current.commit != commit || current.path != location.path {
+		app.push_blame(commit, location.path, true)?;
+	}
+	app.select_line(usize::try_from(line).unwrap_or(1).max(1) - 1);
+	Ok(None)
+}
+
+// reblames the file as of the shown commit, keeping the selected line when the commit is where it came from
+fn blame_at_shown_commit(app: &mut App) -> Result<Option<String>, Box<dyn Error>> {
+	let commit = app.shown_commit.as_ref().unwrap().commit;
+	let current = app.commit_stack.last().unwrap();
+	let selected = app.blame_state.selected().map(|index| &app.blame[index]);
+	// the line's commit may have known the file by another name
+	let (path, line) = match selected {
+		Some(hunk) if hunk.commit == commit => (
+			hunk.path.clone().unwrap_or_else(|| current.path.clone()),
+			Some(hunk.orig_line_num),
+		),
+		_ => (current.path.clone(), None),
+	};
+	if current.commit == commit && current.path == path {
+		return Ok(None);
+	}
+	if !git::path_exists(app.repo, &path, commit) {
+		return Ok(Some(format!(
+			"{} doesn't exist at {:.*}",
+			path.display(),
+			app.abbrev,
+			commit.to_string()
+		)));
+	}
+	let index = app.blame_state.selected();
+	app.push_blame(commit, path, true)?;
+	let index = match line {
+		Some(line) => usize::try_from(line).unwrap_or(1).max(1) - 1,
+		None => index.unwrap_or(0),
+	};
+	app.select_line(index);
+	Ok(None)
+}
+
+// selects the next (or previous) line blamed on the selected line's commit, wrapping around the file
+fn jump_to_same_commit(app: &mut App, forward: bool) {
+	let Some(index) = app.blame_state.selected() else {
+		return;
+	};
+	let commit = app.blame[index].commit;
+	let len = app.blame.len();
+	let found = (1..len)
+		.map(|offset| {
+			if forward {
+				(index + offset) % len
+			} else {
+				(index + len - offset) % len
+			}
+		})
+		.find(|&i| app.blame[i].commit == commit);
+	match found {
+		Some(i) => {
+			if forward == (i < index) {
+				app.status = Some("wrapped around".to_owned());
+			}
+			app.blame_state.select(Some(i));
+		}
+		None => app.status = Some("no other lines from this commit".to_owned()),
+	}
+}
+
+// selects the first (or last) line written by an author `matches`, who the status line calls `who` if there's none
+fn jump_to_author(app: &mut App, last: bool, who: &str, matches: impl Fn(&str) -> bool) -> Option<String> {
+	let is_match = |hunk: &git::BlameHunk| matches(&hunk.meta.author);
+	let found = if last {
+		app.blame.iter().rposition(is_match)
+	} else {
+		app.blame.iter().position(is_match)
+	};
+	match found {
+		Some(index) => {
+			app.select_line(index);
+			None
+		}
+		None => Some(format!("no lines by {}", who)),
+	}
+}
+
+fn half_page(app: &App, term_size: &Rect) -> i16 {
+	i16::try_from(app.scroll_amount.lines(term_size.height)).unwrap_or(i16::MAX)
+}
+
+// a window's worth of lines, keeping one line from the previous window for context
+fn full_page(app: &App, term_size: &Rect) -> i16 {
+	// the header, footer and status rows aren't part of the list
+	let footer = if app.footer { FOOTER_HEIGHT } else { 0 };
+	let visible = term_size.height.saturating_sub(2 + footer);
+	i16::try_from(visible.saturating_sub(1).max(1)).unwrap_or(i16::MAX)
+}
+
+// the panel scrolls by a u16, so anything past its reach in a huge diff can't be scrolled to anyway
+fn panel_height(text: &Text) -> u16 {
+	u16::try_from(text.height()).unwrap_or(u16::MAX)
+}
+
+// how far the panel can scroll, in lines, before the end of `text` comes up from the bottom of `visible` rows
+fn last_panel_scroll(app: &App, text: &Text, visible: u16) -> u16 {
+	if !app.panel_wrap {
+		return panel_height(text).saturating_sub(visible);
+	}
+	// wrapped lines take more than a row each, so count back from the end until the rows fill the panel
+	let width = usize::from(app.panel_width);
+	let mut rows = 0;
+	for (i, line) in text.lines.iter().enumerate().rev() {
+		rows += wrapped_rows(line, width);
+		if rows > usize::from(visible) {
+			return u16::try_from(i + 1).unwrap_or(u16::MAX);
+		}
+	}
+	0
+}
+
+// how many rows `line` takes when broken every `width` columns
+fn wrapped_rows(line: &Line, width: usize) -> usize {
+	line.width().div_ceil(width.max(1)).max(1)
+}
+
+// breaks the panel's lines into rows of `width` columns for whence.panelWrap, keeping their styles. the panel
+// scrolls by line, so this also returns the row each line starts on
+fn wrap_panel(text: Text<'static>, width: usize) -> (Text<'static>, Vec<usize>) {
+	let width = width.max(1);
+	let mut rows = vec![];
+	let mut starts = Vec::with_capacity(text.lines.len());
+	for line in text.lines {
+		starts.push(rows.len());
+		let mut row = vec![];
+		let mut row_width = 0;
+		for span in line.spans {
+			let mut rest: &str = &span.content;
+			while !rest.is_empty() {
+				if row_width == width {
+					rows.push(Line::from(mem::take(&mut row)));
+					row_width = 0;
+				}
+				let (mut head, mut tail) = layout::split_at_width(rest, width - row_width);
+				if head.is_empty() {
+					if row_width > 0 {
+						// a wide character that doesn't fit at the end of the row starts the next one
+						row_width = width;
+						continue;
+					}
+					let split = rest.chars().next().map_or(rest.len(), char::len_utf8);
+					(head, tail) = rest.split_at(split);
+				}
+				row_width += layout::width(head);
+				row.push(Span::styled(head.to_owned(), span.style));
+				rest = tail;
+			}
+		}
+		rows.push(Line::from(row));
+	}
+	(Text::from(rows), starts)
+}
+
 fn scroll(app: &mut App, term_size: &Rect, amount: i16) {
 	match &app.right_panel {
 		Some(line_history) => {
-			let max = u16::try_from(line_history.height())
-				.unwrap()
-				.saturating_sub(term_size.height);
+			let height = panel_height(line_history);
+			let max = if app.shown_commit.is_some() {
+				// any line of a commit can be scrolled to the top for `L`
+				height.saturating_sub(1)
+			} else {
+				last_panel_scroll(app, line_history, term_size.height)
+			};
 			app.line_history_scroll = app.line_history_scroll.saturating_add_signed(amount).clamp(0, max);
 		}
 		None => {
 			match app.blame_state.selected() {
 				Some(index) => {
 					let new_index = index.saturating_add_signed(amount.into());
-					app.blame_state.select(Some(new_index.clamp(0, app.blame.len() - 1)));
+					app.select_line(new_index);
 				}
 				None => {
-					app.blame_state.select(Some(0));
+					app.select_line(0);
 				}
 			};
 		}
 	}
 }
 
-fn handle_search(blame: &[git::BlameHunk<'_>], query: &str, blame_state: &mut ListState, forward: bool) {
-	let range: Box<dyn Iterator<Item = usize>> = if forward {
+// while a line number is being typed after `:`, selects that line so it can be seen before enter. anything else
+// goes back to where the selection was
+fn preview_line(app: &mut App) {
+	let Some(command) = &app.command else {
+		return;
+	};
+	match command.trim().parse::<usize>() {
+		Ok(line) => app.go_to_line(line),
+		Err(_) => {
+			if let Some(origin) = app.command_origin {
+				app.select_line(origin);
+			}
+		}
+	}
+}
+
+// what's typed after `:`. returns a message for the status line
+fn run_command(app: &mut App, command: &str) -> Option<String> {
+	if let Ok(line) = command.parse::<usize>() {
+		app.go_to_line(line);
+		return None;
+	}
+	match command.split_once(' ') {
+		Some(("find", text)) if !text.trim().is_empty() => find_summary(app, text.trim()),
+		Some(("author", name)) if !name.trim().is_empty() => {
+			let name = name.trim();
+			let lowercase = name.to_lowercase();
+			jump_to_author(app, false, name, |author| author.to_lowercase().contains(&lowercase))
+		}
+		_ if command.is_empty() => None,
+		_ => Some(format!(
+			"unknown command: {} (try a line number, find <text> or author <name>)",
+			command
+		)),
+	}
+}
+
+// selects the next line whose commit summary mentions `text`, ignoring case, after the selected line's hunk and
+// wrapping around to the top
+fn find_summary(app: &mut App, text: &str) -> Option<String> {
+	let text = text.to_lowercase();
+	let start = app.blame_state.selected().map_or(0, |index| index + 1);
+	let len = app.blame.len();
+	let found = (start..len).chain(0..start.min(len)).find(|&i| {
+		let hunk = &app.blame[i];
+		hunk.hunk_start && hunk.meta.summary.to_lowercase().contains(&text)
+	});
+	match found {
+		Some(index) => {
+			app.select_line(index);
+			let wrapped = index < start;
+			wrapped.then(|| "find hit bottom, continuing at top".to_owned())
+		}
+		None => Some(format!("no commit summary mentions {}", text)),
+	}
+}
+
+// whether digit `c` picks a parent of the shown commit to diff against (0 for the combined diff). only merges have
+// parents to pick from, so otherwise digits switch tabs
+fn picks_merge_parent(app: &App, c: char) -> bool {
+	let Some(shown) = &app.shown_commit else {
+		return false;
+	};
+	let parent_count = app
+		.repo
+		.find_commit(shown.commit)
+		.map_or(0, |commit| commit.parent_count());
+	parent_count > 1 && c.to_digit(10).is_some_and(|parent| parent as usize <= parent_count)
+}
+
+// selects the next match and returns a message for the status line, if there is one
+fn handle_search(
+	blame: &[git::BlameHunk],
+	query: &str,
+	blame_state: &mut ListState,
+	forward: bool,
+	wrap: bool,
+) -> Option<String> {
+	let (range, wrapped): (Box<dyn Iterator<Item = usize>>, Box<dyn Iterator<Item = usize>>) = if forward {
 		let start = match blame_state.selected() {
 			Some(index) => index + 1,
 			None => 0,
 		};
-		Box::new(start..blame.len())
+		(Box::new(start..blame.len()), Box::new(0..start.min(blame.len())))
 	} else {
 		let end = blame_state.selected().unwrap_or(0);
-		Box::new((0..end).rev())
+		(Box::new((0..end).rev()), Box::new((end..blame.len()).rev()))
 	};
-	for i in range {
-		let line = &blame[i].line.spans.last().unwrap().content;
-		if line.contains(query) {
+	if let Some(i) = range.into_iter().find(|&i| blame[i].code.contains(query)) {
+		blame_state.select(Some(i));
+		return None;
+	}
+	if wrap {
+		if let Some(i) = wrapped.into_iter().find(|&i| blame[i].code.contains(query)) {
 			blame_state.select(Some(i));
-			return;
+			return Some(if forward {
+				"search hit bottom, continuing at top".to_owned()
+			} else {
+				"search hit top, continuing at bottom".to_owned()
+			});
 		}
+		return Some(format!("pattern not found: {}", query));
+	}
+	Some(if forward {
+		format!("search hit bottom without match for: {}", query)
+	} else {
+		format!("search hit top without match for: {}", query)
+	})
+}
+
+// the full name and email, which the author column may have cut short
+fn author_identity(meta: &git::CommitMeta) -> String {
+	identity(&meta.author, &meta.author_mail)
+}
+
+fn identity(name: &str, mail: &str) -> String {
+	if mail.is_empty() {
+		name.to_owned()
+	} else {
+		format!("{} <{}>", name, mail)
+	}
+}
+
+// the footer from `F`: everything about the selected line that the columns leave out
+fn make_footer(hunk: &git::BlameHunk, path: &Path, now: SystemTime, time_zone: git::TimeZone) -> Text<'static> {
+	let meta = &hunk.meta;
+	let author = author_identity(meta);
+	let committer = identity(&meta.committer, &meta.committer_mail);
+	let age = timeago::Formatter::new().convert(now.duration_since(meta.commit_time).unwrap_or_default());
+	let mut committed = format!(
+		", committed {} ({})",
+		time_zone.date(meta.commit_time, meta.commit_tz).to_rfc2822(),
+		age
+	);
+	if committer != author {
+		committed.push_str(&format!(" by {}", committer));
 	}
+	Text::from(vec![
+		Line::from(vec![
+			Span::styled(hunk.commit.to_string(), Style::default().fg(Color::Yellow)),
+			Span::raw(" "),
+			Span::styled(path.display().to_string(), Style::default().fg(Color::LightBlue)),
+		]),
+		Line::from(vec![
+			Span::raw(author),
+			Span::styled(committed, Style::default().fg(Color::DarkGray)),
+		]),
+	])
+}
+
+fn make_line_info(hunk: &git::BlameHunk, time_zone: git::TimeZone) -> Text<'static> {
+	let format_time = |time: SystemTime, offset: i32| time_zone.date(time, offset).to_rfc2822();
+	let mut lines = vec![
+		Line::from(Span::styled(
+			hunk.commit.to_string(),
+			Style::default().fg(Color::Yellow),
+		)),
+		Line::from(format!("author:      {}", author_identity(&hunk.meta))),
+		Line::from(format!(
+			"authored:    {}",
+			format_time(hunk.meta.author_time, hunk.meta.author_tz)
+		)),
+		Line::from(format!(
+			"committed:   {}",
+			format_time(hunk.meta.commit_time, hunk.meta.commit_tz)
+		)),
+	];
+	if hunk.meta.is_rewritten() {
+		lines.push(Line::default());
+		lines.push(Line::from(Span::styled(
+			"authored and committed at different times: rebased, cherry-picked or amended",
+			Style::default().fg(Color::LightRed),
+		)));
+	}
+	lines.push(Line::default());
+	lines.push(Line::from(hunk.meta.summary.clone()));
+	Text::from(lines)
 }
 
 fn make_help_text() -> Text<'static> {
 	let mut help = vec![
-		"h           this help",
+		"h  ?        this help (j/k scroll it, f fills the screen, any other key closes it)",
 		"q  esc      close window",
+		"ctrl-q      quit right away",
+		"ctrl-l      redraw the screen",
+		"ctrl-z      suspend",
 		"",
 		"    moving",
 		"",
 		"j  ↓        down one line",
 		"k  ↑        up one line",
-		"d  pgdown   down half a window",
+		"d  pgdown   down half a window (whence.scroll)",
 		"u  pgup     up half a window",
+		"ctrl-f spc  down a window",
+		"ctrl-b      up a window",
+		"←  →        scroll a panel of long lines left/right",
+		"yl          copy a permalink to the line on its forge",
+		"yp  yP      copy the file's relative/absolute path and line (path:123)",
+		"ym          copy the selected line's commit message",
+		"o           open another file in a new tab",
+		"ctrl-p      find a file to open by fuzzy name",
+		"tab         next file tab",
+		"shift-tab   previous file tab",
 		"G  end      to last line",
-		"g  home     to first line",
+		"gg home     to first line",
 		":123        to line 123",
+		":find text  to the next line whose commit summary mentions text",
+		"ma          set mark a at the current line",
+		"'a          jump to mark a (cleared by b/B)",
+		"gr          blame at the revision from last time (whence.rememberRev)",
+		"]h  [h      next/previous line from the same commit",
+		"ctrl-o      back to where the last jump (G, gg, :123, searches, marks, b, B...) came from",
+		"ctrl-n      forward again",
+		"[A  ]A      first/last line by the selected line's author",
+		":author who first line by an author whose name contains who",
 		"",
 		"    search",
 		"",
@@ -358,18 +2179,176 @@ fn make_help_text() -> Text<'static> {
 		"    git",
 		"",
 		"enter       show commit",
+		"i           details for the selected line",
+		"a           full author name and email of the selected line",
+		"F           show/hide a footer with the selected line's hash, author, committer and path",
+		"1-9         diff a shown merge against that parent",
+		"            otherwise, switch to that file's tab",
+		"0           combined diff of a shown merge",
+		"ctrl-w      ignore whitespace in shown commits (git show -w)",
+		"E           export the shown commit to <hash>.patch",
+		"R           blame the file as of the shown commit (B to go back)",
+		"L           blame the diff line at the top of the panel (B to go back)",
+		"f           show the file as of the line's commit",
 		"w           trace line through history (git -L)",
 		"b           reblame line at parent commit",
 		"B           undo/pop blame stack",
+		"x           ignore the line's commit and reblame (git blame --ignore-rev)",
+		"X           stop ignoring the last commit ignored with x",
+		"",
+		"    display",
+		"",
+		"s           settings",
+		"v           toggle plain file view (no blame)",
+		"H           toggle full commit hashes",
+		"#           show/hide the hash column",
+		"A           show/hide the author column",
+		"I           author initials instead of names",
+		"T           show/hide the time column",
+		"S           show/hide the commit summary column",
+		"<  >        narrow/widen the blame next to the panel (or drag the divider with whence.mouse)",
+		"W           highlight trailing whitespace",
+		"D           mark rewritten commits (author and commit time differ) with *",
+		"Z           show dates in local time, UTC or the committer's timezone (whence.timeZone)",
 	];
 	(help.drain(..).map(Line::from).collect::<Vec<_>>()).into()
 }
 
+fn title_spans<'a>(app: &'a App) -> Vec<Span<'a>> {
+	let commit_path = app.commit_stack.last().unwrap();
+	let mut spans = vec![];
+	if app.tabs.len() > 1 {
+		spans.push(Span::styled(
+			format!("[{}/{}] ", app.active_tab + 1, app.tabs.len()),
+			Style::default().add_modifier(Modifier::BOLD),
+		));
+	}
+	let commit = if commit_path.commit.is_zero() {
+		"staged (the index)".to_owned()
+	} else {
+		commit_path.commit.to_string()
+	};
+	spans.extend([
+		Span::styled(commit, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+		Span::raw(" "),
+		Span::styled(
+			commit_path.path.to_string_lossy(),
+			Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+		),
+	]);
+	if let Some(commit) = app.remembered_rev {
+		spans.push(Span::styled(
+			format!(" (gr: back to {:.*} from last time)", app.abbrev, commit.to_string()),
+			Style::default().fg(Color::DarkGray),
+		));
+	}
+	if !app.blame_options.ignore_revs.is_empty() {
+		let count = app.blame_options.ignore_revs.len();
+		spans.push(Span::styled(
+			format!(" (ignoring {} commit{})", count, if count == 1 { "" } else { "s" }),
+			Style::default().fg(Color::DarkGray),
+		));
+	}
+	if commit_path.from_shown {
+		spans.push(Span::styled(
+			" (from shown commit)",
+			Style::default().fg(Color::DarkGray),
+		));
+	}
+	if let Some(followed_from) = &app.followed_from {
+		spans.push(Span::styled(
+			format!(" (now {})", followed_from.display()),
+			Style::default().fg(Color::LightBlue),
+		));
+	}
+	// local changes and newer commits aren't in this blame, which is easy to forget after a reblame
+	let head = app.repo.head().ok().and_then(|head| head.target());
+	if commit_path.commit.is_zero() {
+		spans.push(Span::raw(" "));
+		spans.push(Span::styled(
+			" staged ",
+			Style::default().fg(Color::Black).bg(Color::Green),
+		));
+	} else if head != Some(commit_path.commit) {
+		spans.push(Span::raw(" "));
+		spans.push(Span::styled(
+			" not HEAD ",
+			Style::default().fg(Color::Black).bg(Color::Yellow),
+		));
+	}
+	spans
+}
+
+// the selected line's commit summary is appended, truncated to whatever room the rest of the title leaves
+fn make_title<'a>(mut spans: Vec<Span<'a>>, summary: Option<&str>, width: u16) -> Line<'a> {
+	let used: usize = spans.iter().map(Span::width).sum();
+	let room = usize::from(width).saturating_sub(used + 2);
+	if let Some(summary) = summary {
+		if room > 0 && !summary.is_empty() {
+			spans.push(Span::styled(
+				format!("  {}", layout::truncate(summary, room)),
+				Style::default().fg(Color::DarkGray),
+			));
+		}
+	}
+	Line::from(spans)
+}
+
+const HINTS: [(&str, &str); 6] = [
+	("h", "help"),
+	("enter", "show"),
+	("w", "trace"),
+	("b", "reblame"),
+	("/", "search"),
+	("q", "quit"),
+];
+
+// as many hints as fit in `width`, dropping the rest
+fn make_hint_bar(width: u16) -> Line<'static> {
+	let mut spans = vec![];
+	let mut used = 0;
+	for (key, action) in HINTS {
+		let len = key.len() + 1 + action.len() + 2;
+		if used + len > usize::from(width) {
+			break;
+		}
+		used += len;
+		spans.push(Span::styled(key, Style::default().fg(Color::Cyan)));
+		spans.push(Span::styled(
+			format!(":{}  ", action),
+			Style::default().fg(Color::DarkGray),
+		));
+	}
+	Line::from(spans)
+}
+
+// below this, there's no room for the header, a line of blame and the status row
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 3;
+
+// what the panel's text is wrapped to, whether or not it's open, leaving room for the border and a scrollbar
+fn panel_text_width(split: u16, term_size: Rect) -> u16 {
+	let chunks = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints([Constraint::Percentage(split), Constraint::Percentage(100 - split)])
+		.split(term_size);
+	chunks[1].width.saturating_sub(2)
+}
+
 fn ui(frame: &mut Frame, app: &mut App) {
+	if frame.size().width < MIN_WIDTH || frame.size().height < MIN_HEIGHT {
+		// everything is drawn again from scratch once the terminal is resized back up
+		let message = Paragraph::new("terminal too small").wrap(Wrap { trim: true });
+		frame.render_widget(message, frame.size());
+		return;
+	}
 	let constraints = if app.right_panel.is_none() {
 		[Constraint::Percentage(100)].as_ref()
 	} else {
-		[Constraint::Percentage(50), Constraint::Percentage(50)].as_ref()
+		&[
+			Constraint::Percentage(app.split),
+			Constraint::Percentage(100 - app.split),
+		][..]
 	};
 	let size = Rect::new(
 		frame.size().x,
@@ -381,59 +2360,363 @@ fn ui(frame: &mut Frame, app: &mut App) {
 		.direction(Direction::Horizontal)
 		.constraints(constraints)
 		.split(size);
+	app.divider = chunks.get(1).map(|panel| panel.x);
 
-	let items: Vec<ListItem> = app.blame.iter().map(|line| ListItem::new(line.line.clone())).collect();
-	let commit_path = app.commit_stack.last().unwrap();
-	let title = Line::from(vec![
-		Span::styled(
-			commit_path.commit.to_string(),
-			Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
-		),
-		Span::raw(" "),
-		Span::styled(
-			commit_path.path.to_str().unwrap(),
-			Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
-		),
-	]);
-	let list = List::new(items)
-		.block(Block::default().title(title))
-		.highlight_style(Style::default().bg(Color::Indexed(237))); // 232 is black, 255 is white; 237 is dark gray
-	frame.render_stateful_widget(list, chunks[0], &mut app.blame_state);
+	// the header gets its own row so it stays put no matter how far the list is scrolled
+	let blame_chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(
+			[
+				Constraint::Length(1),
+				Constraint::Min(0),
+				Constraint::Length(if app.footer { FOOTER_HEIGHT } else { 0 }),
+			]
+			.as_ref(),
+		)
+		.split(chunks[0]);
+	// with only initials in the author column, the full name goes in front of the summary
+	let selected_summary = app.blame_state.selected().map(|index| {
+		let meta = &app.blame[index].meta;
+		if app.columns.author_initials {
+			format!("{}: {}", meta.author, meta.summary)
+		} else {
+			meta.summary.clone()
+		}
+	});
+	let header = Paragraph::new(make_title(
+		title_spans(app),
+		selected_summary.as_deref(),
+		blame_chunks[0].width,
+	));
+	frame.render_widget(header, blame_chunks[0]);
+
+	let now = SystemTime::now();
+	if app.footer {
+		if let Some(index) = app.blame_state.selected() {
+			let hunk = &app.blame[index];
+			let path = hunk.path.as_deref().unwrap_or(&app.commit_stack.last().unwrap().path);
+			frame.render_widget(
+				Paragraph::new(make_footer(hunk, path, now, app.columns.time_zone)),
+				blame_chunks[2],
+			);
+		}
+	}
+	if app.columns.mark_merges {
+		git::find_merges(app.repo, &app.blame);
+	}
+	app.format_lines(now);
+	let widths = &app.formatted.as_ref().unwrap().widths;
+	let (list_area, list_scrollbar) = split_scrollbar(blame_chunks[1], widths.len(), app.scrollbar);
+	// only the rows that fit go to the list, so drawing doesn't copy every line of the file
+	let height = usize::from(list_area.height);
+	let offset = list_offset(
+		app.blame_state.offset(),
+		app.blame_state.selected(),
+		widths.len(),
+		height,
+	);
+	let items: Vec<ListItem> = app.formatted.as_ref().unwrap().lines[offset..]
+		.iter()
+		.take(height)
+		.map(|line| ListItem::new(line.clone()))
+		.collect();
+	let list = List::new(items).highlight_style(app.selection);
+	let mut visible_state =
+		ListState::default().with_selected(app.blame_state.selected().map(|selected| selected - offset));
+	frame.render_stateful_widget(list, list_area, &mut visible_state);
+	*app.blame_state.offset_mut() = offset;
+	mark_truncated_lines(frame, list_area, offset, widths);
+	app.links.clear();
+	if let Some(remote) = &app.link_remote {
+		if app.file_view.is_none() {
+			let visible = app.blame.iter().enumerate().skip(app.blame_state.offset());
+			for (row, (_, hunk)) in (list_area.y..list_area.bottom()).zip(visible) {
+				let Some(offset) = app.columns.hash_offset(hunk.line_num) else {
+					break;
+				};
+				// the hash may not start at the left edge when other columns are put in front of it
+				let offset = (offset as u16).min(list_area.width);
+				let width = (app.columns.hash_width as u16).min(list_area.width - offset);
+				if hunk.hunk_start && width > 0 {
+					app.links.push(link_cells(
+						frame,
+						list_area.x + offset,
+						row,
+						width,
+						remote.commit_url(&hunk.commit.to_string()),
+					));
+				}
+			}
+		}
+	}
+	if let Some(area) = list_scrollbar {
+		render_scrollbar(frame, area, widths.len(), app.blame_state.offset());
+	}
+	if widths.is_empty() && list_area.height > 0 {
+		let middle = Rect::new(list_area.x, list_area.y + list_area.height / 2, list_area.width, 1);
+		let message = Paragraph::new("no blame to show: the file is empty at this revision")
+			.alignment(Alignment::Center)
+			.style(Style::default().fg(Color::DarkGray));
+		frame.render_widget(message, middle);
+	}
 
 	if let Some(log) = &app.right_panel {
-		let paragraph = Paragraph::new(log.clone())
-			.block(Block::default().borders(Borders::LEFT))
-			.scroll((app.line_history_scroll, 0));
-		frame.render_widget(paragraph, chunks[1]);
+		if app.panel_wrap
+			&& app
+				.wrapped_panel
+				.as_ref()
+				.is_none_or(|wrapped| wrapped.width != app.panel_width)
+		{
+			let (text, starts) = wrap_panel(log.clone(), usize::from(app.panel_width));
+			app.wrapped_panel = Some(WrappedPanel {
+				width: app.panel_width,
+				lines: text.lines,
+				starts,
+			});
+		}
+		// the rows the panel scrolls through, the first one drawn and how many rows the top line takes
+		let top = usize::from(app.line_history_scroll);
+		let (lines, scroll, top_rows, x_scroll) = match (&app.wrapped_panel, app.panel_wrap) {
+			(Some(wrapped), true) => {
+				let (start, end) = match wrapped.starts.get(top) {
+					Some(&start) => (
+						start,
+						wrapped.starts.get(top + 1).copied().unwrap_or(wrapped.lines.len()),
+					),
+					None => (0, 0),
+				};
+				(&wrapped.lines, start, end - start, 0)
+			}
+			_ => (&log.lines, top, 1, app.panel_x_scroll),
+		};
+		let rows = lines.len();
+		let (panel_area, panel_scrollbar) = split_scrollbar(chunks[1], rows, app.scrollbar);
+		let mut block = Block::default().borders(Borders::LEFT);
+		if app.ignore_whitespace && app.shown_commit.is_some() {
+			block = block.title(Span::styled(
+				"ignoring whitespace (ctrl-w)",
+				Style::default().fg(Color::DarkGray),
+			));
+		}
+		// only the rows on screen are copied to be drawn
+		let mut visible: Vec<Line> = lines
+			.iter()
+			.skip(scroll)
+			.take(usize::from(panel_area.height))
+			.cloned()
+			.collect();
+		// the top line is the one `L` jumps to
+		if app.shown_commit.is_some() {
+			for row in visible.iter_mut().take(top_rows) {
+				row.patch_style(app.selection);
+			}
+		}
+		let paragraph = Paragraph::new(visible).block(block).scroll((0, x_scroll));
+		frame.render_widget(paragraph, panel_area);
+		// the shown commit's hash heads the panel
+		if let (Some(remote), Some(shown)) = (&app.link_remote, &app.shown_commit) {
+			let width = (FULL_HASH_WIDTH as u16).min(panel_area.width.saturating_sub(1));
+			if app.line_history_scroll == 0 && x_scroll == 0 && width > 0 && panel_area.height > 0 {
+				let url = remote.commit_url(&shown.commit.to_string());
+				app.links
+					.push(link_cells(frame, panel_area.x + 1, panel_area.y, width, url));
+			}
+		}
+		if let Some(area) = panel_scrollbar {
+			render_scrollbar(frame, area, rows, scroll);
+		}
 	}
 
-	let command = match &app.search {
-		Some(search) => Some(format!("/{}", search.query.as_str())),
-		None => app.line_number.as_ref().map(|ln| format!(":{}", ln)),
+	let command = match (&app.search, &app.command, &app.open_path) {
+		(Some(search), _, _) => Some(format!("/{}", search.query.as_str())),
+		(None, Some(command), _) => Some(format!(":{}", command)),
+		(None, None, Some(open_path)) => Some(format!("open (relative to the repository root): {}", open_path)),
+		(None, None, None) => None,
 	};
-	if let Some(cmd_str) = command {
-		let paragraph = Paragraph::new(cmd_str).wrap(Wrap { trim: false });
-		let size = Rect::new(
-			frame.size().x,
-			frame.size().y + frame.size().height - 1,
-			frame.size().width,
-			1,
-		);
-		frame.render_widget(paragraph, size);
+	let status_size = Rect::new(
+		frame.size().x,
+		frame.size().y + frame.size().height - 1,
+		frame.size().width,
+		1,
+	);
+	match (&app.status, command) {
+		(Some(status), _) => frame.render_widget(Paragraph::new(status.as_str()), status_size),
+		(None, Some(cmd_str)) => {
+			let paragraph = Paragraph::new(cmd_str).wrap(Wrap { trim: false });
+			frame.render_widget(paragraph, status_size);
+		}
+		(None, None) => frame.render_widget(Paragraph::new(make_hint_bar(status_size.width)), status_size),
+	}
+
+	if let Some(picker) = &mut app.picker {
+		render_picker(frame, picker, app.selection);
+	}
+
+	if let Some(mut state) = app.settings.take() {
+		render_settings(frame, app, &mut state);
+		app.settings = Some(state);
 	}
 
 	if let Some(popup) = &app.popup {
-		let paragraph = Paragraph::new(popup.clone()).wrap(Wrap { trim: false });
-		let area = centered_rect(80, 80, frame.size());
+		let mut paragraph = Paragraph::new(popup.clone()).wrap(Wrap { trim: false });
+		let mut area = centered_rect(80, 80, frame.size());
+		let mut margin = tui::layout::Margin {
+			vertical: 2,
+			horizontal: 3,
+		};
+		if let Some(help) = &app.help {
+			paragraph = paragraph.scroll((help.scroll, 0));
+			if help.full_screen {
+				area = frame.size();
+				margin = tui::layout::Margin {
+					vertical: 1,
+					horizontal: 2,
+				};
+			}
+		}
 		frame.render_widget(Clear, area);
 		frame.render_widget(Block::default().borders(Borders::all()), area);
-		frame.render_widget(
-			paragraph,
-			area.inner(&tui::layout::Margin {
-				vertical: 2,
-				horizontal: 3,
-			}),
-		);
+		frame.render_widget(paragraph, area.inner(&margin));
+	}
+}
+
+// the settings that have a config key, written to ~/.gitconfig so they apply everywhere
+fn save_settings(app: &App) -> Result<(), git2::Error> {
+	let mut config = git2::Config::open_default()?.open_global()?;
+	for setting in SETTINGS {
+		if let Some(key) = setting.config_key {
+			config.set_bool(key, (setting.get)(app))?;
+		}
+	}
+	Ok(())
+}
+
+fn render_settings(frame: &mut Frame, app: &App, state: &mut ListState) {
+	let area = centered_rect(60, 60, frame.size());
+	frame.render_widget(Clear, area);
+	let block = Block::default().borders(Borders::all()).title(" settings ");
+	let inner = block.inner(area);
+	frame.render_widget(block, area);
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+		.split(inner);
+	let items: Vec<ListItem> = SETTINGS
+		.iter()
+		.map(|setting| {
+			let check = if (setting.get)(app) { "[x]" } else { "[ ]" };
+			let mut spans = vec![Span::raw(format!("{} {}", check, setting.name))];
+			if let Some(key) = setting.config_key {
+				spans.push(Span::styled(format!("  {}", key), Style::default().fg(Color::DarkGray)));
+			}
+			ListItem::new(Line::from(spans))
+		})
+		.collect();
+	let list = List::new(items).highlight_style(app.selection);
+	frame.render_stateful_widget(list, chunks[0], state);
+	frame.render_widget(
+		Paragraph::new(Span::styled(
+			"enter: toggle  w: save to the global git config  esc: close",
+			Style::default().fg(Color::DarkGray),
+		)),
+		chunks[1],
+	);
+}
+
+fn render_picker(frame: &mut Frame, picker: &mut Picker, selection: Style) {
+	let area = centered_rect(80, 80, frame.size());
+	frame.render_widget(Clear, area);
+	let block = Block::default().borders(Borders::all()).title(format!(
+		" {}/{} files ",
+		picker.matches.len(),
+		picker.files.len()
+	));
+	let inner = block.inner(area);
+	frame.render_widget(block, area);
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+		.split(inner);
+	frame.render_widget(
+		Paragraph::new(Line::from(vec![
+			Span::styled("> ", Style::default().fg(Color::Cyan)),
+			Span::raw(picker.query.as_str()),
+		])),
+		chunks[0],
+	);
+	let items: Vec<ListItem> = picker
+		.matches
+		.iter()
+		.map(|&i| ListItem::new(picker.files[i].as_str()))
+		.collect();
+	let list = List::new(items).highlight_style(selection);
+	frame.render_stateful_widget(list, chunks[1], &mut picker.state);
+}
+
+// takes the rightmost column of `area` for a scrollbar when `len` lines don't fit in it
+fn split_scrollbar(area: Rect, len: usize, enabled: bool) -> (Rect, Option<Rect>) {
+	if !enabled || len <= usize::from(area.height) || area.width < 2 {
+		return (area, None);
+	}
+	let content = Rect::new(area.x, area.y, area.width - 1, area.height);
+	let scrollbar = Rect::new(area.right() - 1, area.y, 1, area.height);
+	(content, Some(scrollbar))
+}
+
+fn render_scrollbar(frame: &mut Frame, area: Rect, len: usize, offset: usize) {
+	// ratatui places the thumb by position / content_length and sizes it by viewport / content_length,
+	// so scale the viewport to keep the thumb at the visible fraction of the content
+	let viewport = usize::from(area.height);
+	let max_offset = len.saturating_sub(viewport).max(1);
+	let mut state = ScrollbarState::new(max_offset)
+		.position(offset)
+		.viewport_content_length((viewport * max_offset / len.max(1)).max(1));
+	let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+		.begin_symbol(None)
+		.end_symbol(None)
+		.track_symbol(Some("│"))
+		.track_style(Style::default().fg(Color::DarkGray));
+	frame.render_stateful_widget(scrollbar, area, &mut state);
+}
+
+fn link_cells(frame: &mut Frame, x: u16, y: u16, width: u16, url: String) -> Link {
+	let buffer = frame.buffer_mut();
+	Link {
+		x,
+		y,
+		cells: (x..x + width).map(|x| buffer.get(x, y).clone()).collect(),
+		url,
+	}
+}
+
+// the first row to draw so the selected line is in view, scrolling as little as possible, like `List` does
+fn list_offset(offset: usize, selected: Option<usize>, len: usize, height: usize) -> usize {
+	let last = len.saturating_sub(1);
+	let (offset, selected) = (offset.min(last), selected.unwrap_or(0).min(last));
+	if selected < offset {
+		selected
+	} else if height > 0 && selected >= offset + height {
+		selected + 1 - height
+	} else {
+		offset
+	}
+}
+
+// overwrite the last visible column of lines that didn't fit so it's clear there's more
+fn mark_truncated_lines(frame: &mut Frame, area: Rect, offset: usize, widths: &[usize]) {
+	if area.width == 0 {
+		return;
+	}
+	let visible = widths.iter().skip(offset).take(usize::from(area.height));
+	for (row, width) in visible.enumerate() {
+		if *width > usize::from(area.width) {
+			let y = area.y + u16::try_from(row).unwrap();
+			frame
+				.buffer_mut()
+				.get_mut(area.right() - 1, y)
+				.set_symbol("›")
+				.set_fg(Color::DarkGray);
+		}
 	}
 }
 
@@ -463,3 +2746,350 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 		)
 		.split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{handle_input, list_offset, panel_height, ui, wrap_panel, App, JumpList, Place};
+	use crate::{config::Config, temp_repo::TempRepo};
+	use crossterm::event::{KeyCode, KeyEvent};
+	use git2::Repository;
+	use git_whence::git;
+	use std::path::PathBuf;
+	use tui::{
+		backend::TestBackend,
+		layout::Rect,
+		style::{Color, Style},
+		text::{Line, Span, Text},
+		Terminal,
+	};
+
+	fn app(repo: &Repository) -> App<'_> {
+		App::new(repo, &Config::default(), git::BlameOptions::default())
+	}
+
+	fn press(app: &mut App, code: KeyCode) {
+		handle_input(&KeyEvent::from(code), app, &Rect::new(0, 0, 120, 40)).unwrap();
+	}
+
+	// `:` and then `command`
+	fn run(app: &mut App, command: &str) {
+		for c in format!(":{}", command).chars() {
+			press(app, KeyCode::Char(c));
+		}
+		press(app, KeyCode::Enter);
+	}
+
+	fn place(selected: usize) -> Place {
+		Place {
+			commit_stack: vec![],
+			selected: Some(selected),
+		}
+	}
+
+	#[test]
+	fn jump_list() {
+		let mut jumps = JumpList::default();
+		jumps.record(place(1));
+		jumps.record(place(2));
+		let back = |jumps: &mut JumpList, here| jumps.back(place(here)).and_then(|place| place.selected);
+		let forward = |jumps: &mut JumpList| jumps.forward().and_then(|place| place.selected);
+		assert_eq!(back(&mut jumps, 3), Some(2));
+		assert_eq!(back(&mut jumps, 2), Some(1));
+		assert_eq!(back(&mut jumps, 1), None);
+		assert_eq!(forward(&mut jumps), Some(2));
+		assert_eq!(forward(&mut jumps), Some(3));
+		assert_eq!(forward(&mut jumps), None);
+
+		// jumping from partway back drops the places after it
+		assert_eq!(back(&mut jumps, 3), Some(2));
+		jumps.record(place(2));
+		assert_eq!(forward(&mut jumps), None);
+		assert_eq!(back(&mut jumps, 5), Some(2));
+		assert_eq!(back(&mut jumps, 2), Some(1));
+	}
+
+	#[test]
+	fn digits_pick_merge_parents() {
+		let temp = TempRepo::new("digits");
+		let base = temp.commit("f.txt", "a\n", "edit f.txt", &[]);
+		let side = temp.commit("g.txt", "b\n", "edit g.txt", &[]);
+		let merge = temp.commit("f.txt", "a\nc\n", "edit f.txt", &[base, side]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), merge, None).unwrap();
+		app.open_tab("f.txt".as_ref(), merge, None).unwrap();
+
+		app.show_commit(merge, 1);
+		press(&mut app, KeyCode::Char('2'));
+		assert_eq!(app.shown_commit.as_ref().map(|shown| shown.parent), Some(2));
+		press(&mut app, KeyCode::Char('0'));
+		assert_eq!(app.shown_commit.as_ref().map(|shown| shown.parent), Some(0));
+		assert_eq!(app.active_tab, 1);
+		// the merge has no third parent, so 3 is left for tabs like any other digit
+		press(&mut app, KeyCode::Char('3'));
+		assert_eq!(app.shown_commit.as_ref().map(|shown| shown.parent), Some(0));
+
+		// a commit with one parent has none to pick from
+		app.show_commit(side, 1);
+		press(&mut app, KeyCode::Char('1'));
+		assert_eq!(app.active_tab, 0);
+	}
+
+	#[test]
+	fn jump_to_diff_line() {
+		let temp = TempRepo::new("diff-line");
+		let base = temp.commit("f.txt", "a\nb\nc\n", "edit f.txt", &[]);
+		let change = temp.commit("f.txt", "a\nc\nd\n", "edit f.txt", &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), change, None).unwrap();
+		app.show_commit(change, 1);
+		let locations = app.shown_commit.as_ref().unwrap().locations.clone();
+		let row = |old_line, new_line| {
+			let is_line = |location: &git::DiffLocation| location.old_line == old_line && location.new_line == new_line;
+			let index = locations
+				.iter()
+				.position(|location| location.as_ref().is_some_and(is_line));
+			u16::try_from(index.unwrap()).unwrap()
+		};
+
+		// an added line is in the blame being browsed
+		app.line_history_scroll = row(None, Some(3));
+		press(&mut app, KeyCode::Char('L'));
+		assert_eq!(app.status, None);
+		assert_eq!(app.commit_stack.last().unwrap().commit, change);
+		assert_eq!(app.blame_state.selected(), Some(2));
+
+		// a deleted line is only in the parent's
+		app.show_commit(change, 1);
+		app.line_history_scroll = row(Some(2), None);
+		press(&mut app, KeyCode::Char('L'));
+		assert_eq!(app.commit_stack.last().unwrap().commit, base);
+		assert_eq!(app.blame_state.selected(), Some(1));
+
+		app.show_commit(change, 1);
+		app.line_history_scroll = 0;
+		press(&mut app, KeyCode::Char('L'));
+		assert_eq!(
+			app.status.as_deref(),
+			Some("scroll a line of the diff to the top of the panel first")
+		);
+	}
+
+	#[test]
+	fn jump_from_combined_diff() {
+		let temp = TempRepo::new("combined-diff");
+		let base = temp.commit("f.txt", "a\n", "edit f.txt", &[]);
+		let side = temp.commit("f.txt", "b\n", "edit f.txt", &[]);
+		let merge = temp.commit("f.txt", "c\n", "edit f.txt", &[base, side]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), merge, None).unwrap();
+		app.show_commit(merge, 0);
+		press(&mut app, KeyCode::Char('L'));
+		assert_eq!(
+			app.status.as_deref(),
+			Some("L needs a diff against one parent; pick one with its number")
+		);
+	}
+
+	#[test]
+	fn export_combined_diff() {
+		let temp = TempRepo::new("export");
+		let base = temp.commit("f.txt", "a\n", "edit f.txt", &[]);
+		let side = temp.commit("f.txt", "b\n", "edit f.txt", &[]);
+		let merge = temp.commit("f.txt", "c\n", "edit f.txt", &[base, side]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), merge, None).unwrap();
+		app.show_commit(merge, 0);
+		press(&mut app, KeyCode::Char('E'));
+		assert_eq!(
+			app.status.as_deref(),
+			Some("a combined diff can't be exported; pick a parent with its number first")
+		);
+	}
+
+	#[test]
+	fn fit_panel_rewraps() {
+		let temp = TempRepo::new("fit-panel");
+		let body = "a long paragraph that goes on for longer than the panel is wide";
+		temp.commit("f.txt", "a\n", "edit f.txt", &[]);
+		let commit = temp.commit("f.txt", "b\n", &format!("edit f.txt\n\n{}", body), &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), commit, None).unwrap();
+		app.fit_panel(Rect::new(0, 0, 200, 40));
+		app.show_commit(commit, 1);
+		let lines = app.right_panel.as_ref().unwrap().lines.len();
+		app.line_history_scroll = 2;
+
+		// half of 60 columns, less the border and scrollbar
+		app.fit_panel(Rect::new(0, 0, 60, 40));
+		assert_eq!(app.panel_width, 28);
+		assert!(app.right_panel.as_ref().unwrap().lines.len() > lines);
+		assert_eq!(app.line_history_scroll, 2);
+	}
+
+	#[test]
+	fn panel_scrolls_sideways() {
+		let temp = TempRepo::new("panel-sideways");
+		temp.commit("f.txt", "a\n", "add f.txt", &[]);
+		let commit = temp.commit("f.txt", &format!("{}\n", "b".repeat(200)), "widen f.txt", &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), commit, None).unwrap();
+		app.fit_panel(Rect::new(0, 0, 60, 40));
+		app.show_commit(commit, 1);
+
+		press(&mut app, KeyCode::Right);
+		assert_eq!(app.panel_x_scroll, 14);
+		press(&mut app, KeyCode::Left);
+		assert_eq!(app.panel_x_scroll, 0);
+		// h is help whether or not a panel is open
+		press(&mut app, KeyCode::Char('h'));
+		assert!(app.help.is_some());
+		assert!(app.right_panel.is_some());
+	}
+
+	#[test]
+	fn revisions_to_remember() {
+		let temp = TempRepo::new("tab-revisions");
+		let base = temp.commit("old.txt", "a\n", "edit old.txt", &[]);
+		let head = temp.commit("new.txt", "a\n", "edit new.txt", &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("new.txt".as_ref(), head, None).unwrap();
+		// as if `b` had followed the line back to before a rename
+		app.push_blame(base, PathBuf::from("old.txt"), false).unwrap();
+		app.open_tab("new.txt".as_ref(), head, None).unwrap();
+		assert_eq!(
+			app.tab_revisions(),
+			[(PathBuf::from("old.txt"), Some(base)), (PathBuf::from("new.txt"), None)]
+		);
+	}
+
+	#[test]
+	fn commands() {
+		let temp = TempRepo::new("commands");
+		temp.commit("f.txt", "a\n", "fix parsing", &[]);
+		temp.commit("f.txt", "a\nb\n", "add docs", &[]);
+		let head = temp.commit("f.txt", "a\nb\nc\n", "fix a crash", &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), head, None).unwrap();
+		app.select_line(2);
+
+		run(&mut app, "find FIX");
+		assert_eq!(app.blame_state.selected(), Some(0));
+		assert_eq!(app.status.as_deref(), Some("find hit bottom, continuing at top"));
+		run(&mut app, "find fix");
+		assert_eq!(app.blame_state.selected(), Some(2));
+		assert_eq!(app.status, None);
+		run(&mut app, "find release");
+		assert_eq!(app.blame_state.selected(), Some(2));
+		assert_eq!(app.status.as_deref(), Some("no commit summary mentions release"));
+
+		run(&mut app, "2");
+		assert_eq!(app.blame_state.selected(), Some(1));
+		run(&mut app, "frobnicate");
+		assert_eq!(app.blame_state.selected(), Some(1));
+		assert_eq!(
+			app.status.as_deref(),
+			Some("unknown command: frobnicate (try a line number, find <text> or author <name>)")
+		);
+	}
+
+	#[test]
+	fn jump_to_authors() {
+		let temp = TempRepo::new("authors");
+		temp.commit_as("Ada Lovelace", "f.txt", "a\n", "add a", &[]);
+		temp.commit_as("Grace Hopper", "f.txt", "a\nb\n", "add b", &[]);
+		let head = temp.commit_as("Ada Lovelace", "f.txt", "a\nb\nc\n", "add c", &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), head, None).unwrap();
+
+		app.select_line(0);
+		press(&mut app, KeyCode::Char(']'));
+		press(&mut app, KeyCode::Char('A'));
+		assert_eq!(app.blame_state.selected(), Some(2));
+		press(&mut app, KeyCode::Char('['));
+		press(&mut app, KeyCode::Char('A'));
+		assert_eq!(app.blame_state.selected(), Some(0));
+
+		run(&mut app, "author hopper");
+		assert_eq!(app.blame_state.selected(), Some(1));
+		assert_eq!(app.status, None);
+		run(&mut app, "author Babbage");
+		assert_eq!(app.blame_state.selected(), Some(1));
+		assert_eq!(app.status.as_deref(), Some("no lines by Babbage"));
+	}
+
+	#[test]
+	fn huge_panel_height() {
+		let text = Text::from(vec![Line::from("+"); 70_000]);
+		assert_eq!(panel_height(&text), u16::MAX);
+		assert_eq!(panel_height(&Text::from("a\nb")), 2);
+	}
+
+	#[test]
+	fn list_scrolls_to_selection() {
+		// 100 lines, 10 rows
+		assert_eq!(list_offset(0, Some(5), 100, 10), 0);
+		assert_eq!(list_offset(0, Some(10), 100, 10), 1);
+		assert_eq!(list_offset(50, Some(40), 100, 10), 40);
+		assert_eq!(list_offset(50, Some(55), 100, 10), 50);
+		assert_eq!(list_offset(50, None, 100, 10), 0);
+		// the file got shorter
+		assert_eq!(list_offset(50, Some(99), 20, 10), 19);
+		assert_eq!(list_offset(3, Some(0), 0, 10), 0);
+	}
+
+	#[test]
+	fn draw_wrapped_panel() {
+		let temp = TempRepo::new("wrapped-panel");
+		let commit = temp.commit("f.txt", "a\n", "edit f.txt", &[]);
+		let mut app = app(&temp.repo);
+		app.open_tab("f.txt".as_ref(), commit, None).unwrap();
+		app.panel_wrap = true;
+		app.set_panel(Some(Text::from(vec![Line::from("x".repeat(60)), Line::from("y")])));
+		let size = Rect::new(0, 0, 60, 10);
+		app.fit_panel(size);
+		let mut terminal = Terminal::new(TestBackend::new(size.width, size.height)).unwrap();
+		// the panel's text starts right of its border, halfway across
+		let panel_row = |terminal: &Terminal<TestBackend>, row| terminal.backend().buffer().get(31, row).symbol.clone();
+
+		terminal.draw(|frame| ui(frame, &mut app)).unwrap();
+		let wrapped = app.wrapped_panel.as_ref().unwrap();
+		assert_eq!((wrapped.width, &wrapped.starts[..]), (28, &[0, 3][..]));
+		assert_eq!(panel_row(&terminal, 3), "y");
+		app.line_history_scroll = 1;
+		terminal.draw(|frame| ui(frame, &mut app)).unwrap();
+		assert_eq!(panel_row(&terminal, 0), "y");
+
+		app.fit_panel(Rect::new(0, 0, 40, 10));
+		terminal.draw(|frame| ui(frame, &mut app)).unwrap();
+		assert_eq!(app.wrapped_panel.as_ref().unwrap().starts, [0, 4]);
+		app.set_panel(None);
+		assert!(app.wrapped_panel.is_none());
+	}
+
+	#[test]
+	fn wrap_panel_lines() {
+		let green = Style::default().fg(Color::Green);
+		let text = Text::from(vec![
+			Line::from(vec![Span::raw("+"), Span::styled("abcdéfg", green)]),
+			Line::default(),
+			Line::from("xyz"),
+		]);
+		let (wrapped, starts) = wrap_panel(text, 3);
+		assert_eq!(starts, [0, 3, 4]);
+		assert_eq!(
+			wrapped.lines,
+			[
+				Line::from(vec![Span::raw("+"), Span::styled("ab", green)]),
+				Line::from(vec![Span::styled("cdé", green)]),
+				Line::from(vec![Span::styled("fg", green)]),
+				Line::default(),
+				Line::from("xyz"),
+			]
+		);
+
+		// a wide character that would straddle the edge moves to the next row
+		let (wrapped, starts) = wrap_panel(Text::from("a日本🦀"), 3);
+		assert_eq!(starts, [0]);
+		assert_eq!(wrapped.lines, [Line::from("a日"), Line::from("本"), Line::from("🦀")]);
+	}
+}